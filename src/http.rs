@@ -1,14 +1,19 @@
 use std::borrow::Borrow;
-use native_tls::TlsConnector;
+#[cfg(unix)]
+use std::path::Path;
+use http::response::Parts;
+use http_body_util::Collected;
+use hyper::body::Bytes;
+use crate::http_client::HttpClient;
 use crate::http_errors::HttpError;
+#[cfg(feature = "json")]
+use crate::http_request::HttpRequest;
 use crate::http_status_tests::{is_success, StatusTest};
-use crate::imp::hyper::HyperHttp;
-
-// In the future this will be conditional compiled to
-// different implementations based on crate features.
-static HTTP_IMP: HyperHttp = HyperHttp {};
+use crate::tls::TlsConnector;
 
 /// Get text response from a server that is not using TLS/HTTPS.
+///
+/// Connections are kept open for later calls on the same thread.
 pub async fn get_text_http<U>(url: U) -> Result<String, HttpError>
 where
     U: Borrow<str>
@@ -22,10 +27,17 @@ where
     T: StatusTest,
     BT: Borrow<T>
 {
-    HTTP_IMP.get_text_http(url.borrow(), status_test.borrow()).await
+    HttpClient::shared()
+        .get_text_with(url.borrow(), status_test.borrow())
+        .await
 }
 
 /// Get text response from a server that is using TLS/HTTPS.
+///
+/// Each call opens a new connection and repeats the TLS handshake, because a connector
+/// can't be matched to the connections of earlier calls. When calling repeatedly, such
+/// as when polling, use an [`HttpClient`] or an [`HttpsProbe`](crate::waiter::HttpsProbe),
+/// which keep connections open.
 pub async fn get_text_https<U>(url: U, tls: TlsConnector) -> Result<String, HttpError>
 where
     U: Borrow<str>
@@ -39,5 +51,171 @@ where
     T: StatusTest,
     BT: Borrow<T>
 {
-    HTTP_IMP.get_text_https(url.borrow(), tls, status_test.borrow()).await
+    HttpClient::with_tls(tls)
+        .get_text_with(url.borrow(), status_test.borrow())
+        .await
+}
+
+/// Get a JSON response from a server, over TLS if the URL is `https`.
+///
+/// # Example
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use passivized_test_support::http::get_json;
+/// use passivized_test_support::http_errors::HttpError;
+///
+/// async fn example() -> Result<(), HttpError> {
+///     let health: HashMap<String, bool> = get_json("http://localhost:8200/v1/sys/health").await?;
+///
+///     assert_eq!(Some(&false), health.get("sealed"));
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "json")]
+pub async fn get_json<T, U>(url: U) -> Result<T, HttpError>
+where
+    T: serde::de::DeserializeOwned,
+    U: Borrow<str>
+{
+    HttpRequest::get(url.borrow())
+        .header("Accept", "application/json")
+        .send()
+        .await?
+        .json()
+}
+
+/// Post a JSON body to a server, over TLS if the URL is `https`, and get a JSON response.
+#[cfg(feature = "json")]
+pub async fn post_json<T, U, B>(url: U, body: &B) -> Result<T, HttpError>
+where
+    T: serde::de::DeserializeOwned,
+    U: Borrow<str>,
+    B: serde::Serialize + ?Sized
+{
+    let body = serde_json::to_vec(body)
+        .map_err(|error| HttpError::Other(Box::new(error)))?;
+
+    HttpRequest::post(url.borrow())
+        .header("Accept", "application/json")
+        .header("Content-Type", "application/json")
+        .body(body)
+        .send()
+        .await?
+        .json()
+}
+
+/// Send a single gRPC request over HTTP/2, without TLS.
+pub(crate) async fn post_grpc(url: &str, message: Bytes) -> Result<(Parts, Collected<Bytes>), HttpError> {
+    crate::imp::hyper::post_grpc(url, message).await
+}
+
+/// Get text response from a server listening on a Unix domain socket.
+///
+/// The path is the path and query of the request, such as `/_ping`.
+#[cfg(unix)]
+pub async fn get_text_unix<S, U>(socket: S, path: U) -> Result<String, HttpError>
+where
+    S: AsRef<Path>,
+    U: Borrow<str>
+{
+    get_text_unix_with(socket, path, is_success()).await
+}
+
+#[cfg(unix)]
+pub(crate) async fn get_text_unix_with<S, U, T, BT>(socket: S, path: U, status_test: BT) -> Result<String, HttpError>
+where
+    S: AsRef<Path>,
+    U: Borrow<str>,
+    T: StatusTest,
+    BT: Borrow<T>
+{
+    crate::imp::hyper::get_text_unix(socket.as_ref(), path.borrow(), status_test.borrow()).await
+}
+
+#[cfg(all(test, feature = "json"))]
+mod test_json {
+    use std::collections::HashMap;
+    use mockito::Matcher;
+    use crate::http_errors::HttpError;
+    use super::{get_json, post_json};
+
+    #[tokio::test]
+    async fn gets() {
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("GET", "/health")
+            .match_header("accept", "application/json")
+            .with_body("{\"initialized\": true, \"sealed\": false}")
+            .create_async()
+            .await;
+
+        let actual: HashMap<String, bool> = get_json(format!("{}/health", server.url()))
+            .await
+            .unwrap();
+
+        assert_eq!(Some(&true), actual.get("initialized"));
+        assert_eq!(Some(&false), actual.get("sealed"));
+    }
+
+    #[tokio::test]
+    async fn invalid_json_has_body() {
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("GET", "/health")
+            .with_body("<html>Bad Gateway</html>")
+            .create_async()
+            .await;
+
+        let actual = get_json::<serde_json::Value, _>(format!("{}/health", server.url()))
+            .await
+            .unwrap_err();
+
+        if let HttpError::Json { body, .. } = actual {
+            assert_eq!("<html>Bad Gateway</html>", body);
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_json_body_is_truncated() {
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("GET", "/large")
+            .with_body("x".repeat(1000))
+            .create_async()
+            .await;
+
+        let actual = get_json::<serde_json::Value, _>(format!("{}/large", server.url()))
+            .await
+            .unwrap_err();
+
+        if let HttpError::Json { body, .. } = actual {
+            assert_eq!(format!("{}...", "x".repeat(256)), body);
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+    }
+
+    #[tokio::test]
+    async fn posts() {
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("POST", "/jobs")
+            .match_header("content-type", "application/json")
+            .match_body(Matcher::Json(serde_json::json!({"name": "example", "priority": 2})))
+            .with_status(201)
+            .with_body("{\"id\": 7}")
+            .create_async()
+            .await;
+
+        let actual: serde_json::Value = post_json(format!("{}/jobs", server.url()), &serde_json::json!({"name": "example", "priority": 2}))
+            .await
+            .unwrap();
+
+        assert_eq!(serde_json::json!({"id": 7}), actual);
+    }
 }
@@ -0,0 +1,122 @@
+use regex::Regex;
+
+pub fn contains<S: Into<String>>(expected: S) -> Contains {
+    Contains::new(expected.into())
+}
+
+pub fn matches(pattern: Regex) -> Matches {
+    Matches::new(pattern)
+}
+
+/// Expect the body to be a JSON document, with the value at a JSON pointer
+/// (RFC 6901), such as `/status` or `/health/0/ready`, equal to a given value.
+#[cfg(feature = "json")]
+pub fn json_pointer_equals<P: Into<String>>(pointer: P, expected: serde_json::Value) -> JsonPointerEquals {
+    JsonPointerEquals::new(pointer.into(), expected)
+}
+
+pub trait BodyTest {
+    fn test(&self, body: &str) -> bool;
+}
+
+impl<T: BodyTest + ?Sized> BodyTest for &T {
+    fn test(&self, body: &str) -> bool {
+        (**self).test(body)
+    }
+}
+
+#[derive(Clone)]
+pub struct Contains {
+    expected: String
+}
+
+impl Contains {
+    fn new(expected: String) -> Self {
+        Self {
+            expected
+        }
+    }
+}
+
+impl BodyTest for Contains {
+    fn test(&self, body: &str) -> bool {
+        body.contains(&self.expected)
+    }
+}
+
+#[derive(Clone)]
+pub struct Matches {
+    pattern: Regex
+}
+
+impl Matches {
+    fn new(pattern: Regex) -> Self {
+        Self {
+            pattern
+        }
+    }
+}
+
+impl BodyTest for Matches {
+    fn test(&self, body: &str) -> bool {
+        self.pattern.is_match(body)
+    }
+}
+
+#[cfg(feature = "json")]
+#[derive(Clone)]
+pub struct JsonPointerEquals {
+    pointer: String,
+    expected: serde_json::Value
+}
+
+#[cfg(feature = "json")]
+impl JsonPointerEquals {
+    fn new(pointer: String, expected: serde_json::Value) -> Self {
+        Self {
+            pointer,
+            expected
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl BodyTest for JsonPointerEquals {
+    fn test(&self, body: &str) -> bool {
+        match serde_json::from_str::<serde_json::Value>(body) {
+            Ok(document) => document.pointer(&self.pointer) == Some(&self.expected),
+            Err(_) => false
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_body_tests {
+    use regex::Regex;
+    use super::{contains, matches, BodyTest};
+
+    #[test]
+    fn contains_substring() {
+        assert!(contains("ready").test("{\"status\":\"ready\"}"));
+        assert!(!contains("ready").test("{\"status\":\"starting\"}"));
+    }
+
+    #[test]
+    fn matches_pattern() {
+        let test = matches(Regex::new(r#""status":\s*"(ok|ready)""#).unwrap());
+
+        assert!(test.test("{\"status\": \"ok\"}"));
+        assert!(!test.test("{\"status\": \"starting\"}"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_pointer_equals() {
+        let test = super::json_pointer_equals("/sealed", serde_json::Value::Bool(false));
+
+        assert!(test.test("{\"initialized\":true,\"sealed\":false}"));
+        assert!(!test.test("{\"initialized\":true,\"sealed\":true}"));
+        assert!(!test.test("{\"initialized\":true}"));
+        assert!(!test.test("not json"));
+    }
+}
@@ -0,0 +1,255 @@
+use std::borrow::Borrow;
+use std::cell::RefCell;
+use http::Method;
+use http_body_util::Full;
+use hyper::body::Bytes;
+use log::warn;
+
+use crate::http_errors::HttpError;
+use crate::http_request::HttpRequest;
+use crate::http_response::HttpResponse;
+use crate::http_status_tests::{is_success, StatusTest};
+use crate::imp::HttpImp;
+use crate::tls::TlsConnector;
+
+thread_local! {
+    // Per thread, because pooled connections are driven by tasks on the runtime
+    // that opened them, and tests usually each have their own runtime.
+    static SHARED: RefCell<Option<HttpClient>> = const { RefCell::new(None) };
+}
+
+/// Client for HTTP and HTTPS that keeps connections open between requests.
+///
+/// Clones share connections, so a client can be created once and cloned into
+/// each place that needs it, such as repeated readiness checks over TLS.
+///
+/// # Example
+///
+/// ```rust
+/// use http::Method;
+/// use passivized_test_support::http_client::HttpClient;
+/// use passivized_test_support::http_errors::HttpError;
+///
+/// async fn example() -> Result<(), HttpError> {
+///     let client = HttpClient::new()?;
+///
+///     for _ in 0..100 {
+///         client.request(Method::POST, "https://localhost:8443/events")
+///             .body("{}")
+///             .send()
+///             .await?;
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct HttpClient {
+    imp: HttpImp
+}
+
+impl HttpClient {
+
+    /// Client using the system defaults for TLS.
+    pub fn new() -> Result<Self, HttpError> {
+        let tls = TlsConnector::new()
+            .map_err(|error| HttpError::Other(Box::new(error)))?;
+
+        Ok(Self::with_tls(tls))
+    }
+
+    /// Client using a TLS connector, such as one that trusts a test certificate authority.
+    pub fn with_tls(tls: TlsConnector) -> Self {
+        Self {
+            imp: HttpImp::new(tls, true)
+        }
+    }
+
+    /// Client shared by the free functions in [`http`](crate::http) on the current thread.
+    pub(crate) fn shared() -> Self {
+        SHARED.with(|shared| {
+            let mut shared = shared.borrow_mut();
+
+            if let Some(client) = shared.as_ref() {
+                return client.clone();
+            }
+
+            let client = Self::with_platform_tls(true);
+            *shared = Some(client.clone());
+
+            client
+        })
+    }
+
+    /// Client that opens a new connection for every request, so that each request
+    /// sees whether the server is still accepting connections.
+    pub(crate) fn unpooled() -> Self {
+        Self::with_platform_tls(false)
+    }
+
+    /// If the platform's TLS setup fails, the client still makes plain HTTP requests,
+    /// and reports the failure for HTTPS URLs.
+    fn with_platform_tls(pooled: bool) -> Self {
+        let imp = match TlsConnector::new() {
+            Ok(tls) => HttpImp::new(tls, pooled),
+            Err(error) => {
+                warn!("Only plain HTTP is available, because TLS could not be set up: {}", error);
+                HttpImp::without_tls(error, pooled)
+            }
+        };
+
+        Self {
+            imp
+        }
+    }
+
+    /// Get the text of a response, which must have a success status.
+    pub async fn get_text<U: Borrow<str>>(&self, url: U) -> Result<String, HttpError> {
+        self.get_text_with(url.borrow(), &is_success()).await
+    }
+
+    pub(crate) async fn get_text_with<T>(&self, url: &str, status_test: &T) -> Result<String, HttpError>
+    where
+        T: StatusTest + ?Sized
+    {
+        self.imp.get_text(url, status_test).await
+    }
+
+    /// Build a request to send with this client.
+    pub fn request<U: Into<String>>(&self, method: Method, url: U) -> HttpRequest {
+        HttpRequest::new(method, url)
+            .client(self)
+    }
+
+    pub(crate) async fn send<T>(&self, request: hyper::http::Request<Full<Bytes>>, status_test: &T) -> Result<HttpResponse, HttpError>
+    where
+        T: StatusTest + ?Sized
+    {
+        self.imp.send(request, status_test).await
+    }
+}
+
+#[cfg(test)]
+mod test_http_client {
+    use std::convert::Infallible;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use http::{Method, Request, Response};
+    use http_body_util::Full;
+    use hyper::body::{Bytes, Incoming};
+    use hyper::service::service_fn;
+    use hyper_util::rt::TokioIo;
+    use tokio::net::TcpListener;
+    use crate::tls::test_server::{accept, connector};
+    use super::HttpClient;
+
+    /// Keep-alive server that counts the connections it accepts.
+    async fn serve(tls: bool) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap();
+
+        let port = listener.local_addr()
+            .unwrap()
+            .port();
+
+        let url = if tls {
+            format!("https://localhost:{}/", port)
+        }
+        else {
+            format!("http://127.0.0.1:{}/", port)
+        };
+
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept()
+                    .await
+                    .unwrap();
+
+                counter.fetch_add(1, Ordering::SeqCst);
+
+                let service = service_fn(|request: Request<Incoming>| async move {
+                    Ok::<_, Infallible>(Response::new(Full::new(Bytes::from(request.method().to_string()))))
+                });
+
+                tokio::spawn(async move {
+                    let connection = hyper::server::conn::http1::Builder::new();
+
+                    if tls {
+                        if let Ok(stream) = accept(stream).await {
+                            let _ = connection.serve_connection(TokioIo::new(stream), service).await;
+                        }
+                    }
+                    else {
+                        let _ = connection.serve_connection(TokioIo::new(stream), service).await;
+                    }
+                });
+            }
+        });
+
+        (url, connections)
+    }
+
+    #[tokio::test]
+    async fn clones_share_connections() {
+        let (url, connections) = serve(false).await;
+
+        let client = HttpClient::new()
+            .unwrap();
+
+        for _ in 0..3 {
+            assert_eq!("GET", client.get_text(url.as_str()).await.unwrap());
+        }
+
+        let actual = client.clone()
+            .request(Method::DELETE, url.as_str())
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!("DELETE", actual.text().unwrap());
+        assert_eq!(1, connections.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn free_functions_share_connections() {
+        let (url, connections) = serve(false).await;
+
+        for _ in 0..3 {
+            crate::http::get_text_http(url.as_str())
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(1, connections.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn free_https_function_connects_per_call() {
+        let (url, connections) = serve(true).await;
+
+        for _ in 0..3 {
+            crate::http::get_text_https(url.as_str(), connector())
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(3, connections.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn shares_connections_over_tls() {
+        let (url, connections) = serve(true).await;
+
+        let client = HttpClient::with_tls(connector());
+
+        for _ in 0..3 {
+            assert_eq!("GET", client.get_text(url.as_str()).await.unwrap());
+        }
+
+        assert_eq!(1, connections.load(Ordering::SeqCst));
+    }
+}
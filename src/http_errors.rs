@@ -10,11 +10,22 @@ pub enum HttpError {
     #[error("{0}")]
     Http(http::Error),
 
+    /// A response body was not the expected JSON; contains the start of the body.
+    #[cfg(feature = "json")]
+    #[error("Invalid JSON response body ({error}): {body}")]
+    Json {
+        error: serde_json::Error,
+        body: String
+    },
+
     #[error("{0}")]
     Other(Box<dyn Error>),
 
     #[error("{0}")]
-    Status(StatusCode)
+    Status(StatusCode),
+
+    #[error("Unexpected response body: {0}")]
+    UnexpectedBody(String)
 }
 
 impl From<FromUtf8Error> for HttpError {
@@ -23,6 +34,12 @@ impl From<FromUtf8Error> for HttpError {
     }
 }
 
+impl From<std::io::Error> for HttpError {
+    fn from(other: std::io::Error) -> Self {
+        Self::Other(Box::new(other))
+    }
+}
+
 impl From<http::Error> for HttpError {
     fn from(other: http::Error) -> Self {
         Self::Http(other)
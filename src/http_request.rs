@@ -0,0 +1,255 @@
+use http::Method;
+use http_body_util::Full;
+use hyper::body::Bytes;
+
+use crate::http_client::HttpClient;
+use crate::http_errors::HttpError;
+use crate::http_response::HttpResponse;
+use crate::http_status_tests::{is_success, StatusTest};
+use crate::tls::TlsConnector;
+
+/// Builder for a request with any method, headers, query parameters, and body.
+///
+/// URLs starting with `https` are sent over TLS, using the system defaults unless
+/// a connector or client is given. Without a client, connections are shared with
+/// the free functions in [`http`](crate::http).
+///
+/// # Example
+///
+/// ```rust
+/// use passivized_test_support::http_errors::HttpError;
+/// use passivized_test_support::http_request::HttpRequest;
+/// use passivized_test_support::http_status_tests::equals;
+///
+/// async fn example() -> Result<(), HttpError> {
+///     HttpRequest::post("http://localhost:8080/jobs")
+///         .header("Content-Type", "application/json")
+///         .query("priority", "high")
+///         .body("{\"name\": \"example\"}")
+///         .expect_status(equals(http::StatusCode::CREATED))
+///         .send()
+///         .await?;
+///
+///     Ok(())
+/// }
+/// ```
+pub struct HttpRequest {
+    method: Method,
+    url: String,
+    query: Vec<(String, String)>,
+    headers: Vec<(String, String)>,
+    body: Bytes,
+    client: Option<HttpClient>,
+    status_test: Box<dyn StatusTest + Send + Sync>
+}
+
+impl HttpRequest {
+
+    pub fn new<U: Into<String>>(method: Method, url: U) -> Self {
+        Self {
+            method,
+            url: url.into(),
+            query: Vec::new(),
+            headers: Vec::new(),
+            body: Bytes::new(),
+            client: None,
+            status_test: Box::new(is_success())
+        }
+    }
+
+    pub fn delete<U: Into<String>>(url: U) -> Self {
+        Self::new(Method::DELETE, url)
+    }
+
+    pub fn get<U: Into<String>>(url: U) -> Self {
+        Self::new(Method::GET, url)
+    }
+
+    pub fn patch<U: Into<String>>(url: U) -> Self {
+        Self::new(Method::PATCH, url)
+    }
+
+    pub fn post<U: Into<String>>(url: U) -> Self {
+        Self::new(Method::POST, url)
+    }
+
+    pub fn put<U: Into<String>>(url: U) -> Self {
+        Self::new(Method::PUT, url)
+    }
+
+    /// Add a header. Invalid names or values are reported when the request is sent.
+    pub fn header<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Add a query parameter, which is URL encoded.
+    pub fn query<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+        self.query.push((name.into(), value.into()));
+        self
+    }
+
+    /// Set the body, from a string or bytes.
+    pub fn body<B: Into<Bytes>>(mut self, body: B) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Connector for TLS, such as one that trusts a test certificate authority.
+    ///
+    /// The request is sent on a new connection.
+    pub fn tls(self, tls: TlsConnector) -> Self {
+        self.client(&HttpClient::with_tls(tls))
+    }
+
+    /// Client to send the request with, reusing its connections.
+    pub fn client(mut self, client: &HttpClient) -> Self {
+        self.client = Some(client.clone());
+        self
+    }
+
+    /// Status that the response must have. Defaults to any success status.
+    pub fn expect_status<T: StatusTest + Send + Sync + 'static>(mut self, status_test: T) -> Self {
+        self.status_test = Box::new(status_test);
+        self
+    }
+
+    fn uri(&self) -> String {
+        if self.query.is_empty() {
+            return self.url.clone();
+        }
+
+        let query = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(&self.query)
+            .finish();
+
+        // The query goes before any fragment, which is never sent to the server.
+        let (url, fragment) = match self.url.split_once('#') {
+            Some((url, fragment)) => (url, format!("#{}", fragment)),
+            None => (self.url.as_str(), String::new())
+        };
+
+        let separator = if url.contains('?') { '&' } else { '?' };
+
+        format!("{}{}{}{}", url, separator, query, fragment)
+    }
+
+    /// Send the request, returning the response if it has the expected status.
+    pub async fn send(self) -> Result<HttpResponse, HttpError> {
+        let mut builder = hyper::http::Request::builder()
+            .method(self.method.clone())
+            .uri(self.uri());
+
+        for (name, value) in &self.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+
+        let request = builder.body(Full::new(self.body))?;
+
+        let client = match self.client {
+            Some(client) => client,
+            None => HttpClient::shared()
+        };
+
+        client.send(request, self.status_test.as_ref()).await
+    }
+}
+
+#[cfg(test)]
+mod test_http_request {
+    use http::StatusCode;
+    use mockito::Matcher;
+    use crate::http_errors::HttpError;
+    use crate::http_status_tests::equals;
+    use super::HttpRequest;
+
+    #[test]
+    fn appends_query_before_fragment() {
+        let request = HttpRequest::get("http://localhost/p?x=1#frag")
+            .query("a", "b c");
+
+        assert_eq!("http://localhost/p?x=1&a=b+c#frag", request.uri());
+
+        let request = HttpRequest::get("http://localhost/p#frag")
+            .query("a", "b");
+
+        assert_eq!("http://localhost/p?a=b#frag", request.uri());
+    }
+
+    #[tokio::test]
+    async fn checks_status() {
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("DELETE", "/items/1")
+            .with_status(204)
+            .create_async()
+            .await;
+
+        let actual = HttpRequest::delete(format!("{}/items/1", server.url()))
+            .expect_status(equals(StatusCode::OK))
+            .send()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual, HttpError::Status(StatusCode::NO_CONTENT)), "{:?}", actual);
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_header() {
+        let actual = HttpRequest::get("http://localhost:1/")
+            .header("bad header", "value")
+            .send()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual, HttpError::Http(_)), "{:?}", actual);
+    }
+
+    #[tokio::test]
+    async fn sends_bytes_body() {
+        let mut server = mockito::Server::new_async().await;
+
+        let mock = server.mock("PUT", "/blob")
+            .match_body(vec![0u8, 159, 146, 150])
+            .create_async()
+            .await;
+
+        HttpRequest::put(format!("{}/blob", server.url()))
+            .body(vec![0u8, 159, 146, 150])
+            .send()
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn sends_method_headers_query_and_body() {
+        let mut server = mockito::Server::new_async().await;
+
+        let mock = server.mock("POST", "/jobs")
+            .match_header("x-request-id", "abc")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("existing".into(), "1".into()),
+                Matcher::UrlEncoded("name".into(), "a b&c".into()),
+            ]))
+            .match_body("{\"id\":1}")
+            .with_status(201)
+            .with_body("created")
+            .create_async()
+            .await;
+
+        let actual = HttpRequest::post(format!("{}/jobs?existing=1", server.url()))
+            .header("X-Request-Id", "abc")
+            .query("name", "a b&c")
+            .body("{\"id\":1}")
+            .send()
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+
+        assert_eq!(StatusCode::CREATED, actual.status());
+        assert_eq!("created", actual.text().unwrap());
+    }
+}
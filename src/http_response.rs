@@ -0,0 +1,148 @@
+use http::{HeaderMap, StatusCode, Version};
+use http::response::Parts;
+use hyper::body::Bytes;
+
+use crate::http_errors::HttpError;
+#[cfg(feature = "json")]
+use crate::snippets::snippet;
+
+/// A response, with its status, headers, and complete body.
+///
+/// # Example
+///
+/// ```rust
+/// use passivized_test_support::http_errors::HttpError;
+/// use passivized_test_support::http_request::HttpRequest;
+///
+/// async fn example() -> Result<(), HttpError> {
+///     let response = HttpRequest::post("http://localhost:8080/jobs")
+///         .body("{}")
+///         .send()
+///         .await?;
+///
+///     assert_eq!(Some("application/json"), response.header("Content-Type"));
+///     println!("Created {:?}: {}", response.header("Location"), response.text()?);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct HttpResponse {
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+    body: Bytes
+}
+
+impl HttpResponse {
+
+    pub(crate) fn new(parts: Parts, body: Bytes) -> Self {
+        Self {
+            status: parts.status,
+            version: parts.version,
+            headers: parts.headers,
+            body
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Value of a header, by case-insensitive name, if present and valid text.
+    ///
+    /// For a header with several values, this is the first.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)?
+            .to_str()
+            .ok()
+    }
+
+    pub fn bytes(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// The body as text, failing if it is not UTF-8.
+    pub fn text(&self) -> Result<String, HttpError> {
+        Ok(String::from_utf8(self.body.to_vec())?)
+    }
+
+    /// The body parsed as JSON.
+    #[cfg(feature = "json")]
+    pub fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T, HttpError> {
+        serde_json::from_slice(&self.body)
+            .map_err(|error| HttpError::Json { error, body: snippet(&self.body) })
+    }
+
+    pub fn into_bytes(self) -> Bytes {
+        self.body
+    }
+
+    pub fn into_text(self) -> Result<String, HttpError> {
+        Ok(String::from_utf8(self.body.into())?)
+    }
+}
+
+#[cfg(test)]
+mod test_http_response {
+    use http::{StatusCode, Version};
+    use crate::http_errors::HttpError;
+    use crate::http_request::HttpRequest;
+
+    #[tokio::test]
+    async fn keeps_headers_and_binary_body() {
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("GET", "/image")
+            .with_header("Content-Type", "image/png")
+            .with_header("ETag", "\"abc\"")
+            .with_body([0x89u8, b'P', b'N', b'G', 0xff])
+            .create_async()
+            .await;
+
+        let actual = HttpRequest::get(format!("{}/image", server.url()))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, actual.status());
+        assert_eq!(Version::HTTP_11, actual.version());
+        assert_eq!(Some("image/png"), actual.header("content-type"));
+        assert_eq!(Some("\"abc\""), actual.header("ETag"));
+        assert_eq!(None, actual.header("Location"));
+        assert_eq!(&[0x89u8, b'P', b'N', b'G', 0xff][..], &actual.bytes()[..]);
+
+        let error = actual.text()
+            .unwrap_err();
+
+        assert!(matches!(error, HttpError::FromUtf8(_)), "{:?}", error);
+    }
+
+    #[tokio::test]
+    async fn redirect_location() {
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("POST", "/jobs")
+            .with_status(303)
+            .with_header("Location", "/jobs/7")
+            .create_async()
+            .await;
+
+        let actual = HttpRequest::post(format!("{}/jobs", server.url()))
+            .expect_status(crate::http_status_tests::equals(StatusCode::SEE_OTHER))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(Some("/jobs/7"), actual.header("location"));
+        assert_eq!("", actual.into_text().unwrap());
+    }
+}
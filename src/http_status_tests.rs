@@ -4,6 +4,11 @@ pub fn equals(status: StatusCode) -> Equals {
     Equals::new(status)
 }
 
+/// Accept any status, for when any response at all shows the server is up.
+pub fn is_any() -> Any {
+    Any{}
+}
+
 pub fn is_success() -> Success {
     Success{}
 }
@@ -12,6 +17,15 @@ pub trait StatusTest {
     fn test(&self, status: StatusCode) -> bool;
 }
 
+#[derive(Copy, Clone)]
+pub struct Any {}
+
+impl StatusTest for Any {
+    fn test(&self, _status: StatusCode) -> bool {
+        true
+    }
+}
+
 #[derive(Copy, Clone)]
 pub struct Equals {
     expected: StatusCode
@@ -1,89 +1,167 @@
-use http::Response;
-use http_body_util::{BodyExt, Empty};
-use hyper::body::{Body, Bytes, Incoming};
-use hyper_util::client::legacy::Client;
+#[cfg(unix)]
+use std::path::Path;
+use std::sync::Arc;
+use http::{Request, Response};
+use http::uri::Scheme;
+use http::response::Parts;
+use http_body_util::{BodyExt, Collected, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper_util::client::legacy::{Builder, Client};
 use hyper_util::client::legacy::connect::HttpConnector;
 use hyper_util::rt::TokioExecutor;
-use native_tls::TlsConnector;
+#[cfg(unix)]
+use hyper_util::rt::TokioIo;
+#[cfg(unix)]
+use log::warn;
+#[cfg(unix)]
+use tokio::net::UnixStream;
 use crate::http_status_tests::StatusTest;
 use crate::http_errors::HttpError;
+use crate::http_response::HttpResponse;
+use crate::tls::{TlsConnector, TlsError};
+use super::TlsBackend;
 
-pub(crate) struct HyperHttp {}
+/// Pooled client for HTTP, and for HTTPS using a TLS connector. Clones share the pool.
+#[derive(Clone)]
+pub(crate) struct HyperHttp<B: TlsBackend> {
+    client: Pool<B::Connector>
+}
+
+#[derive(Clone)]
+enum Pool<C> {
+    Tls(Client<C, Full<Bytes>>),
+
+    /// Plain HTTP only, keeping the reason TLS could not be set up to report for HTTPS URLs.
+    Plain(Client<HttpConnector, Full<Bytes>>, Arc<TlsError>)
+}
 
-const MAX_IDLE_PER_HOST: usize = 1;
+impl<B: TlsBackend> HyperHttp<B> {
 
-impl HyperHttp {
+    /// Client that keeps idle connections open for reuse only if `pooled`.
+    pub(crate) fn new(tls: TlsConnector, pooled: bool) -> Self {
+        let client = builder(pooled)
+            .build(B::connector(tls));
 
-    fn build_client_http<D: Send, B: Body<Data = D> + Send>() -> Client<HttpConnector, B> {
-        Client::builder(TokioExecutor::new())
-            .pool_max_idle_per_host(MAX_IDLE_PER_HOST)
-            .build(HttpConnector::new())
+        Self {
+            client: Pool::Tls(client)
+        }
     }
 
-    fn build_client_from_tls<D: Send, B: Body<Data = D> + Send>(tls: TlsConnector) -> Client<hyper_tls::HttpsConnector<HttpConnector>, B> {
-        let mut inner = HttpConnector::new();
-        inner.enforce_http(false);
+    /// Client for plain HTTP, for when a TLS connector could not be built.
+    pub(crate) fn without_tls(error: TlsError, pooled: bool) -> Self {
+        let client = builder(pooled)
+            .build(HttpConnector::new());
 
-        Client::builder(TokioExecutor::new())
-            .pool_max_idle_per_host(MAX_IDLE_PER_HOST)
-            .build(hyper_tls::HttpsConnector::from((inner, tls.into())))
+        Self {
+            client: Pool::Plain(client, Arc::new(error))
+        }
     }
 
-    pub(crate) async fn get_text_http<T>(&self, url: &str, status_test: &T) -> Result<String, HttpError>
+    pub(crate) async fn get_text<T>(&self, url: &str, status_test: &T) -> Result<String, HttpError>
     where
-        T: StatusTest
+        T: StatusTest + ?Sized
     {
-        let client = Self::build_client_http();
-
         let request = hyper::http::Request::get(url)
-            .body(empty_body())?;
+            .body(Full::default())?;
 
-        let response = client
-            .request(request)
-            .await?;
-
-        Self::parse_response(response, status_test).await
+        self.send(request, status_test)
+            .await?
+            .into_text()
     }
 
-    pub(crate) async fn get_text_https<T>(&self, url: &str, tls: TlsConnector, status_test: &T) -> Result<String, HttpError>
+    pub(crate) async fn send<T>(&self, request: Request<Full<Bytes>>, status_test: &T) -> Result<HttpResponse, HttpError>
     where
-        T: StatusTest
+        T: StatusTest + ?Sized
     {
-        let client = Self::build_client_from_tls(tls);
+        let response = match &self.client {
+            Pool::Tls(client) => client.request(request).await?,
+            Pool::Plain(_, error) if request.uri().scheme() == Some(&Scheme::HTTPS) => {
+                return Err(HttpError::Other(Box::new(error.clone())));
+            }
+            Pool::Plain(client, _) => client.request(request).await?
+        };
 
-        let request = hyper::http::Request::get(url)
-            .body(empty_body())?;
+        parse_response(response, status_test).await
+    }
+}
 
-        let response = client
-            .request(request)
-            .await?;
+fn builder(pooled: bool) -> Builder {
+    let mut builder = Client::builder(TokioExecutor::new());
 
-        Self::parse_response(response, status_test).await
+    if !pooled {
+        builder.pool_max_idle_per_host(0);
     }
 
-    async fn parse_response<T>(response: Response<Incoming>, status_test: &T) -> Result<String, HttpError>
-    where
-        T: StatusTest
-    {
-        if status_test.test(response.status()) {
-            let response_body = incoming_bytes(response).await?;
+    builder
+}
 
-            Ok(String::from_utf8(response_body)?)
-        }
-        else {
-            Err(HttpError::Status(response.status()))
+/// Send a single gRPC request over HTTP/2 without TLS, returning the response
+/// head and the full body, including any trailers.
+pub(crate) async fn post_grpc(url: &str, message: Bytes) -> Result<(Parts, Collected<Bytes>), HttpError> {
+    let client: Client<HttpConnector, Full<Bytes>> = Client::builder(TokioExecutor::new())
+        .http2_only(true)
+        .build(HttpConnector::new());
+
+    let request = hyper::http::Request::post(url)
+        .header(hyper::header::CONTENT_TYPE, "application/grpc")
+        .header(hyper::header::TE, "trailers")
+        .body(Full::new(message))?;
+
+    let (parts, body) = client
+        .request(request)
+        .await?
+        .into_parts();
+
+    Ok((parts, body.collect().await?))
+}
+
+#[cfg(unix)]
+pub(crate) async fn get_text_unix<T>(socket: &Path, path_and_query: &str, status_test: &T) -> Result<String, HttpError>
+where
+    T: StatusTest
+{
+    let stream = UnixStream::connect(socket).await?;
+
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream)).await?;
+
+    tokio::spawn(async move {
+        if let Err(error) = connection.await {
+            warn!("Unix socket connection failed: {:?}", error);
         }
-    }
+    });
+
+    let request = hyper::http::Request::get(path_and_query)
+        .header(hyper::header::HOST, "localhost")
+        .body(Full::<Bytes>::default())?;
+
+    let response = sender
+        .send_request(request)
+        .await?;
+
+    parse_response(response, status_test)
+        .await?
+        .into_text()
 }
 
-fn empty_body() -> Empty<Bytes> {
-    Empty::new()
+async fn parse_response<T>(response: Response<Incoming>, status_test: &T) -> Result<HttpResponse, HttpError>
+where
+    T: StatusTest + ?Sized
+{
+    if status_test.test(response.status()) {
+        let (parts, body) = response.into_parts();
+        let response_body = incoming_bytes(body).await?;
+
+        Ok(HttpResponse::new(parts, response_body.into()))
+    }
+    else {
+        Err(HttpError::Status(response.status()))
+    }
 }
 
-async fn incoming_bytes(mut response: Response<Incoming>) -> Result<Vec<u8>, hyper::Error> {
+async fn incoming_bytes(mut body: Incoming) -> Result<Vec<u8>, hyper::Error> {
     let mut response_body: Vec<u8> = Vec::new();
 
-    while let Some(frame_result) = response.frame().await {
+    while let Some(frame_result) = body.frame().await {
         let frame = frame_result?;
 
         if let Some(segment) = frame.data_ref() {
@@ -97,15 +175,20 @@ async fn incoming_bytes(mut response: Response<Incoming>) -> Result<Vec<u8>, hyp
 #[cfg(test)]
 mod test_get_text_from_http {
     use http::StatusCode;
-    use super::HyperHttp;
     use crate::http_errors::HttpError;
     use crate::http_status_tests::is_success;
+    use crate::imp::HttpImp;
+    use crate::tls::{Certificate, TlsConnector};
+
+    fn client() -> HttpImp {
+        HttpImp::new(TlsConnector::new().unwrap(), true)
+    }
 
     #[tokio::test]
     async fn fails_when_server_error() {
         let server = mockito::Server::new_async().await;
 
-        let actual = HyperHttp{}.get_text_http(&format!("{}/qux", server.url()), &is_success())
+        let actual = client().get_text(&format!("{}/qux", server.url()), &is_success())
             .await
             .unwrap_err();
 
@@ -121,7 +204,7 @@ mod test_get_text_from_http {
     async fn fails_when_server_not_present() {
         let server = "http://127.0.0.200:1234";
 
-        HyperHttp{}.get_text_http(&format!("{}/foo", server), &is_success())
+        client().get_text(&format!("{}/foo", server), &is_success())
             .await
             .unwrap_err();
     }
@@ -135,10 +218,39 @@ mod test_get_text_from_http {
             .create_async()
             .await;
 
-        let actual = HyperHttp{}.get_text_http(&format!("{}/foo", server.url()), &is_success())
+        let actual = client().get_text(&format!("{}/foo", server.url()), &is_success())
+            .await
+            .unwrap();
+
+        assert_eq!("bar", actual);
+    }
+
+    #[tokio::test]
+    async fn gets_without_tls() {
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("GET", "/foo")
+            .with_body("bar")
+            .create_async()
+            .await;
+
+        let error = Certificate::from_pem(b"not a certificate")
+            .err()
+            .unwrap();
+
+        let expected = error.to_string();
+        let client = HttpImp::without_tls(error, true);
+
+        let actual = client.get_text(&format!("{}/foo", server.url()), &is_success())
             .await
             .unwrap();
 
         assert_eq!("bar", actual);
+
+        let actual = client.get_text("https://localhost/foo", &is_success())
+            .await
+            .unwrap_err();
+
+        assert_eq!(expected, actual.to_string());
     }
 }
\ No newline at end of file
@@ -1 +1,53 @@
-pub(crate) mod hyper;
\ No newline at end of file
+use std::future::Future;
+use std::pin::Pin;
+use hyper_util::client::legacy::connect::Connect;
+use tokio::net::TcpStream;
+use crate::tls::{TlsConnector, TlsError};
+
+pub(crate) mod hyper;
+#[cfg(feature = "native-tls")]
+pub(crate) mod native_tls;
+#[cfg(all(feature = "rustls", not(feature = "native-tls")))]
+pub(crate) mod rustls;
+
+// Selected by crate feature; native-tls wins when both are enabled.
+#[cfg(feature = "native-tls")]
+pub(crate) type TlsImp = native_tls::NativeTls;
+#[cfg(all(feature = "rustls", not(feature = "native-tls")))]
+pub(crate) type TlsImp = rustls::Rustls;
+#[cfg(not(any(feature = "native-tls", feature = "rustls")))]
+pub(crate) type TlsImp = NoTls;
+
+pub(crate) type HttpImp = hyper::HyperHttp<TlsImp>;
+
+pub(crate) type HandshakeFuture<'a> = Pin<Box<dyn Future<Output = Result<Option<Vec<u8>>, TlsError>> + Send + 'a>>;
+
+/// The parts of the HTTP client and the TLS probe that depend on the TLS crate.
+pub(crate) trait TlsBackend: Clone {
+    type Connector: Connect + Clone + Send + Sync + 'static;
+
+    /// Connector for both HTTP and HTTPS URLs.
+    fn connector(tls: TlsConnector) -> Self::Connector;
+
+    /// Complete a TLS handshake, returning the DER encoded certificate the server presented, if any.
+    fn handshake<'a>(tls: &'a TlsConnector, host: &'a str, stream: TcpStream) -> HandshakeFuture<'a>;
+}
+
+/// Stands in for a backend when neither TLS feature is enabled, so that the
+/// `compile_error!` in [`tls`](crate::tls) is the only error reported.
+#[cfg(not(any(feature = "native-tls", feature = "rustls")))]
+#[derive(Clone)]
+pub(crate) enum NoTls {}
+
+#[cfg(not(any(feature = "native-tls", feature = "rustls")))]
+impl TlsBackend for NoTls {
+    type Connector = hyper_util::client::legacy::connect::HttpConnector;
+
+    fn connector(_tls: TlsConnector) -> Self::Connector {
+        unreachable!()
+    }
+
+    fn handshake<'a>(_tls: &'a TlsConnector, _host: &'a str, _stream: TcpStream) -> HandshakeFuture<'a> {
+        unreachable!()
+    }
+}
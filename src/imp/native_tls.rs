@@ -0,0 +1,34 @@
+use hyper_tls::HttpsConnector;
+use hyper_util::client::legacy::connect::HttpConnector;
+use tokio::net::TcpStream;
+use crate::tls::TlsConnector;
+use super::{HandshakeFuture, TlsBackend};
+
+/// TLS using the platform's TLS library, through the native-tls crate.
+#[derive(Clone)]
+pub(crate) struct NativeTls;
+
+impl TlsBackend for NativeTls {
+    type Connector = HttpsConnector<HttpConnector>;
+
+    fn connector(tls: TlsConnector) -> Self::Connector {
+        let mut inner = HttpConnector::new();
+        inner.enforce_http(false);
+
+        HttpsConnector::from((inner, tls.into()))
+    }
+
+    fn handshake<'a>(tls: &'a TlsConnector, host: &'a str, stream: TcpStream) -> HandshakeFuture<'a> {
+        Box::pin(async move {
+            let connector = tokio_native_tls::TlsConnector::from(tls.clone());
+
+            let stream = connector.connect(host, stream)
+                .await?;
+
+            match stream.get_ref().peer_certificate()? {
+                None => Ok(None),
+                Some(certificate) => Ok(Some(certificate.to_der()?))
+            }
+        })
+    }
+}
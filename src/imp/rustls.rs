@@ -0,0 +1,43 @@
+use std::io;
+use hyper_rustls::HttpsConnector;
+use hyper_util::client::legacy::connect::HttpConnector;
+use rustls::pki_types::ServerName;
+use tokio::net::TcpStream;
+use crate::tls::TlsConnector;
+use super::{HandshakeFuture, TlsBackend};
+
+/// TLS using rustls, for builds without OpenSSL.
+#[derive(Clone)]
+pub(crate) struct Rustls;
+
+impl TlsBackend for Rustls {
+    type Connector = HttpsConnector<HttpConnector>;
+
+    fn connector(tls: TlsConnector) -> Self::Connector {
+        let mut inner = HttpConnector::new();
+        inner.enforce_http(false);
+
+        hyper_rustls::HttpsConnectorBuilder::new()
+            .with_tls_config((*tls.config()).clone())
+            .https_or_http()
+            .enable_all_versions()
+            .wrap_connector(inner)
+    }
+
+    fn handshake<'a>(tls: &'a TlsConnector, host: &'a str, stream: TcpStream) -> HandshakeFuture<'a> {
+        Box::pin(async move {
+            let server_name = ServerName::try_from(host.to_string())
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+
+            let stream = tokio_rustls::TlsConnector::from(tls.config())
+                .connect(server_name, stream)
+                .await?;
+
+            let (_, connection) = stream.get_ref();
+
+            Ok(connection.peer_certificates()
+                .and_then(|certificates| certificates.first())
+                .map(|certificate| certificate.to_vec()))
+        })
+    }
+}
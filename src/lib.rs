@@ -2,11 +2,18 @@
 pub mod cli;
 pub mod env;
 pub mod http;
+pub mod http_body_tests;
+pub mod http_client;
 pub mod http_errors;
+pub mod http_request;
+pub mod http_response;
 pub mod http_status_tests;
 pub mod logging;
 pub mod retry;
 pub mod timestamps;
+pub mod tls;
+pub mod tls_certificate_tests;
 pub mod waiter;
 
 pub(crate) mod imp;
+pub(crate) mod snippets;
@@ -44,7 +44,7 @@ impl Backoff for Limit {
 
     fn next_backoff(&mut self) -> Option<Duration> {
         if self.remaining > 0 {
-            self.remaining = self.remaining - 1;
+            self.remaining -= 1;
             self.policy.next_backoff()
         }
         else {
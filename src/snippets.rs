@@ -0,0 +1,12 @@
+/// Longest excerpt kept in errors.
+const SNIPPET: usize = 256;
+
+/// The start of some contents, which need not be UTF-8, for an error message.
+pub(crate) fn snippet(contents: &[u8]) -> String {
+    let text = String::from_utf8_lossy(contents);
+
+    match text.char_indices().nth(SNIPPET) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.into_owned()
+    }
+}
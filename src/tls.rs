@@ -0,0 +1,231 @@
+//! TLS client configuration for the [`http`](crate::http) and [`waiter`](crate::waiter) modules.
+//!
+//! The implementation is chosen by crate feature: `native-tls` (the default) re-exports
+//! the types of the native-tls crate, and `rustls` provides types with the same names.
+//!
+//! The two are not identical. Under `rustls`:
+//!
+//! - [`TlsConnectorBuilder`] only has `add_root_certificate`, `danger_accept_invalid_certs`
+//!   and `build`. There is no client identity, protocol version range, SNI or ALPN setting,
+//!   and no `danger_accept_invalid_hostnames`.
+//! - [`TlsConnector`] only has `new` and `builder`, but converts from a
+//!   `rustls::ClientConfig` for anything the builder doesn't cover.
+//! - [`Certificate`] only has `from_der`, `from_pem` and `to_der`.
+//! - [`TlsError`] is [`std::io::Error`] rather than `native_tls::Error`.
+//!
+//! Code using only those items builds with either feature.
+
+#[cfg(feature = "native-tls")]
+pub use native_tls::{Certificate, Error as TlsError, TlsConnector, TlsConnectorBuilder};
+
+#[cfg(all(feature = "rustls", not(feature = "native-tls")))]
+pub use self::rustls_connector::{Certificate, TlsConnector, TlsConnectorBuilder, TlsError};
+
+#[cfg(not(any(feature = "native-tls", feature = "rustls")))]
+compile_error!("Either the native-tls or the rustls feature must be enabled.");
+
+#[cfg(all(feature = "rustls", not(feature = "native-tls")))]
+mod rustls_connector {
+    use std::io;
+    use std::sync::Arc;
+    use log::warn;
+    use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::crypto::CryptoProvider;
+    use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+    use rustls::pki_types::pem::PemObject;
+
+    /// Errors are reported as I/O errors, as by tokio-rustls.
+    pub type TlsError = io::Error;
+
+    /// An X.509 certificate, such as a test certificate authority.
+    #[derive(Clone, Debug)]
+    pub struct Certificate(CertificateDer<'static>);
+
+    impl Certificate {
+        pub fn from_der(der: &[u8]) -> Result<Self, TlsError> {
+            Ok(Self(CertificateDer::from(der.to_vec())))
+        }
+
+        /// Parse the first certificate in a PEM file.
+        pub fn from_pem(pem: &[u8]) -> Result<Self, TlsError> {
+            CertificateDer::from_pem_slice(pem)
+                .map(Self)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+        }
+
+        pub fn to_der(&self) -> Result<Vec<u8>, TlsError> {
+            Ok(self.0.to_vec())
+        }
+    }
+
+    /// Client side TLS configuration. Clones share the configuration.
+    #[derive(Clone)]
+    pub struct TlsConnector {
+        config: Arc<ClientConfig>
+    }
+
+    impl TlsConnector {
+        /// Connector trusting the platform's root certificates.
+        pub fn new() -> Result<Self, TlsError> {
+            Self::builder().build()
+        }
+
+        pub fn builder() -> TlsConnectorBuilder {
+            TlsConnectorBuilder {
+                accept_invalid_certs: false,
+                root_certificates: Vec::new()
+            }
+        }
+
+        pub(crate) fn config(&self) -> Arc<ClientConfig> {
+            self.config.clone()
+        }
+    }
+
+    impl From<ClientConfig> for TlsConnector {
+        fn from(config: ClientConfig) -> Self {
+            Self {
+                config: Arc::new(config)
+            }
+        }
+    }
+
+    pub struct TlsConnectorBuilder {
+        accept_invalid_certs: bool,
+        root_certificates: Vec<Certificate>
+    }
+
+    impl TlsConnectorBuilder {
+        /// Trust a certificate authority in addition to the platform's root certificates.
+        pub fn add_root_certificate(&mut self, certificate: Certificate) -> &mut Self {
+            self.root_certificates.push(certificate);
+            self
+        }
+
+        /// Accept any server certificate. Only for servers with throwaway certificates.
+        pub fn danger_accept_invalid_certs(&mut self, accept_invalid_certs: bool) -> &mut Self {
+            self.accept_invalid_certs = accept_invalid_certs;
+            self
+        }
+
+        pub fn build(&self) -> Result<TlsConnector, TlsError> {
+            let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+
+            let builder = ClientConfig::builder_with_provider(provider.clone())
+                .with_safe_default_protocol_versions()
+                .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+
+            let config = if self.accept_invalid_certs {
+                builder
+                    .dangerous()
+                    .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate { provider }))
+                    .with_no_client_auth()
+            }
+            else {
+                builder
+                    .with_root_certificates(self.root_store()?)
+                    .with_no_client_auth()
+            };
+
+            Ok(config.into())
+        }
+
+        fn root_store(&self) -> Result<RootCertStore, TlsError> {
+            let native = rustls_native_certs::load_native_certs();
+
+            for error in &native.errors {
+                warn!("Could not load platform root certificates: {}", error);
+            }
+
+            let mut roots = RootCertStore::empty();
+            roots.add_parsable_certificates(native.certs);
+
+            for certificate in &self.root_certificates {
+                roots.add(certificate.0.clone())
+                    .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+            }
+
+            Ok(roots)
+        }
+    }
+
+    /// Checks handshake signatures, but not the certificate chain or the server name.
+    #[derive(Debug)]
+    struct AcceptAnyCertificate {
+        provider: Arc<CryptoProvider>
+    }
+
+    impl ServerCertVerifier for AcceptAnyCertificate {
+        fn verify_server_cert(
+            &self,
+            _end_entity: &CertificateDer<'_>,
+            _intermediates: &[CertificateDer<'_>],
+            _server_name: &ServerName<'_>,
+            _ocsp_response: &[u8],
+            _now: UnixTime
+        ) -> Result<ServerCertVerified, rustls::Error> {
+            Ok(ServerCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(&self, message: &[u8], cert: &CertificateDer<'_>, dss: &DigitallySignedStruct) -> Result<HandshakeSignatureValid, rustls::Error> {
+            rustls::crypto::verify_tls12_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+        }
+
+        fn verify_tls13_signature(&self, message: &[u8], cert: &CertificateDer<'_>, dss: &DigitallySignedStruct) -> Result<HandshakeSignatureValid, rustls::Error> {
+            rustls::crypto::verify_tls13_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.provider.signature_verification_algorithms.supported_schemes()
+        }
+    }
+}
+
+/// Server side of TLS connections for tests, presenting `test_data/localhost.crt`,
+/// which is issued by `test_data/ca.crt`.
+#[cfg(test)]
+pub(crate) mod test_server {
+    use tokio::io::{AsyncRead, AsyncWrite};
+    use tokio::net::TcpStream;
+    use super::{Certificate, TlsConnector};
+
+    const AUTHORITY: &[u8] = include_bytes!("../test_data/ca.crt");
+    const CERTIFICATE: &[u8] = include_bytes!("../test_data/localhost.crt");
+    const KEY: &[u8] = include_bytes!("../test_data/localhost.key");
+
+    /// Connector trusting the test certificate authority.
+    pub(crate) fn connector() -> TlsConnector {
+        TlsConnector::builder()
+            .add_root_certificate(Certificate::from_pem(AUTHORITY).unwrap())
+            .build()
+            .unwrap()
+    }
+
+    #[cfg(feature = "native-tls")]
+    pub(crate) async fn accept(stream: TcpStream) -> Result<impl AsyncRead + AsyncWrite + Send + Unpin, super::TlsError> {
+        let identity = native_tls::Identity::from_pkcs8(CERTIFICATE, KEY)?;
+
+        tokio_native_tls::TlsAcceptor::from(native_tls::TlsAcceptor::new(identity)?)
+            .accept(stream)
+            .await
+    }
+
+    #[cfg(all(feature = "rustls", not(feature = "native-tls")))]
+    pub(crate) async fn accept(stream: TcpStream) -> Result<impl AsyncRead + AsyncWrite + Send + Unpin, super::TlsError> {
+        use std::sync::Arc;
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+        use rustls::pki_types::pem::PemObject;
+
+        let config = rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::aws_lc_rs::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![CertificateDer::from_pem_slice(CERTIFICATE).unwrap()], PrivateKeyDer::from_pem_slice(KEY).unwrap())
+            .unwrap();
+
+        tokio_rustls::TlsAcceptor::from(Arc::new(config))
+            .accept(stream)
+            .await
+    }
+}
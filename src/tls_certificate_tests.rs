@@ -0,0 +1,194 @@
+use std::net::IpAddr;
+use std::time::Duration;
+use time::OffsetDateTime;
+use x509_parser::certificate::X509Certificate;
+use x509_parser::extensions::GeneralName;
+use x509_parser::prelude::FromDer;
+
+/// The parts of a certificate presented by a server that are useful to check
+/// in tests.
+#[derive(Clone, Debug)]
+pub struct PeerCertificate {
+    pub subject: String,
+    pub common_names: Vec<String>,
+    pub dns_names: Vec<String>,
+    pub ip_addresses: Vec<IpAddr>,
+    pub not_before: OffsetDateTime,
+    pub not_after: OffsetDateTime
+}
+
+impl PeerCertificate {
+
+    pub fn from_der(der: &[u8]) -> Result<Self, String> {
+        let (_, certificate) = X509Certificate::from_der(der)
+            .map_err(|e| e.to_string())?;
+
+        let common_names = certificate.subject()
+            .iter_common_name()
+            .filter_map(|name| name.as_str().ok())
+            .map(String::from)
+            .collect();
+
+        let mut dns_names = Vec::new();
+        let mut ip_addresses = Vec::new();
+
+        let alternative_names = certificate.subject_alternative_name()
+            .map_err(|e| e.to_string())?;
+
+        if let Some(extension) = alternative_names {
+            for name in &extension.value.general_names {
+                match name {
+                    GeneralName::DNSName(dns_name) => dns_names.push(dns_name.to_string()),
+                    GeneralName::IPAddress(octets) => {
+                        if let Some(ip_address) = ip_address(octets) {
+                            ip_addresses.push(ip_address);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(Self {
+            subject: certificate.subject().to_string(),
+            common_names,
+            dns_names,
+            ip_addresses,
+            not_before: certificate.validity().not_before.to_datetime(),
+            not_after: certificate.validity().not_after.to_datetime()
+        })
+    }
+
+}
+
+fn ip_address(octets: &[u8]) -> Option<IpAddr> {
+    if let Ok(v4) = <[u8; 4]>::try_from(octets) {
+        Some(IpAddr::from(v4))
+    }
+    else if let Ok(v6) = <[u8; 16]>::try_from(octets) {
+        Some(IpAddr::from(v6))
+    }
+    else {
+        None
+    }
+}
+
+pub fn has_common_name<S: Into<String>>(expected: S) -> HasCommonName {
+    HasCommonName::new(expected.into())
+}
+
+/// Expect a DNS name in the subject alternative names.
+pub fn has_dns_name<S: Into<String>>(expected: S) -> HasDnsName {
+    HasDnsName::new(expected.into())
+}
+
+/// Expect the certificate to be currently valid, and to remain valid for at
+/// least the given duration.
+pub fn is_valid_for(duration: Duration) -> ValidFor {
+    ValidFor::new(duration)
+}
+
+pub trait CertificateTest {
+    fn test(&self, certificate: &PeerCertificate) -> bool;
+}
+
+#[derive(Clone)]
+pub struct HasCommonName {
+    expected: String
+}
+
+impl HasCommonName {
+    fn new(expected: String) -> Self {
+        Self {
+            expected
+        }
+    }
+}
+
+impl CertificateTest for HasCommonName {
+    fn test(&self, certificate: &PeerCertificate) -> bool {
+        certificate.common_names.contains(&self.expected)
+    }
+}
+
+#[derive(Clone)]
+pub struct HasDnsName {
+    expected: String
+}
+
+impl HasDnsName {
+    fn new(expected: String) -> Self {
+        Self {
+            expected
+        }
+    }
+}
+
+impl CertificateTest for HasDnsName {
+    fn test(&self, certificate: &PeerCertificate) -> bool {
+        certificate.dns_names.contains(&self.expected)
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct ValidFor {
+    duration: Duration
+}
+
+impl ValidFor {
+    fn new(duration: Duration) -> Self {
+        Self {
+            duration
+        }
+    }
+}
+
+impl CertificateTest for ValidFor {
+    fn test(&self, certificate: &PeerCertificate) -> bool {
+        let now = OffsetDateTime::now_utc();
+
+        certificate.not_before <= now && now + self.duration <= certificate.not_after
+    }
+}
+
+#[cfg(test)]
+mod test_peer_certificate {
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::Duration;
+    use super::{has_common_name, has_dns_name, is_valid_for, CertificateTest, PeerCertificate};
+
+    const CERTIFICATE: &[u8] = include_bytes!("../test_data/localhost.crt");
+
+    fn parse() -> PeerCertificate {
+        let pem = crate::tls::Certificate::from_pem(CERTIFICATE)
+            .unwrap();
+
+        PeerCertificate::from_der(&pem.to_der().unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn parses() {
+        let actual = parse();
+
+        assert_eq!("CN=localhost, O=passivized_test_support", actual.subject);
+        assert_eq!(vec!["localhost"], actual.common_names);
+        assert_eq!(vec!["localhost"], actual.dns_names);
+        assert_eq!(vec![IpAddr::V4(Ipv4Addr::LOCALHOST)], actual.ip_addresses);
+        assert!(actual.not_before < actual.not_after);
+    }
+
+    #[test]
+    fn tests() {
+        let certificate = parse();
+
+        assert!(has_common_name("localhost").test(&certificate));
+        assert!(!has_common_name("example.com").test(&certificate));
+
+        assert!(has_dns_name("localhost").test(&certificate));
+        assert!(!has_dns_name("example.com").test(&certificate));
+
+        assert!(is_valid_for(Duration::from_secs(86_400)).test(&certificate));
+        assert!(!is_valid_for(Duration::from_secs(200 * 365 * 86_400)).test(&certificate));
+    }
+}
@@ -0,0 +1,192 @@
+use std::net::SocketAddr;
+use backoff::backoff::Backoff;
+use futures_util::future::join_all;
+use log::{debug, info};
+use tokio::net::TcpStream;
+
+use super::dns::resolve;
+use super::errors::AddressProbeError;
+use super::{wait_for_with_options, Probe, ProbeFuture, WaitError, WaitOptions};
+
+/// Which of the addresses a host name resolves to must accept connections.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AddressPolicy {
+    Any,
+    All
+}
+
+/// Which of the addresses a host name resolved to accepted a connection.
+#[derive(Debug)]
+pub struct AddressReport {
+    pub reachable: Vec<SocketAddr>,
+    pub unreachable: Vec<(SocketAddr, std::io::Error)>
+}
+
+impl AddressReport {
+    fn satisfies(&self, policy: AddressPolicy) -> bool {
+        match policy {
+            AddressPolicy::Any => !self.reachable.is_empty(),
+            AddressPolicy::All => self.unreachable.is_empty()
+        }
+    }
+}
+
+/// Connect to every address, IPv4 and IPv6, that a host name resolves to,
+/// concurrently, rather than stopping at the first that accepts.
+///
+/// Fails if the connections do not satisfy the policy, with a report of which
+/// addresses were reachable.
+pub async fn connect_tcp_server_addresses(host: &str, port: u16, policy: AddressPolicy) -> Result<AddressReport, AddressProbeError> {
+    let addresses = resolve(host, port)
+        .await
+        .map_err(AddressProbeError::Unresolved)?;
+
+    connect_addresses(addresses, policy).await
+}
+
+async fn connect_addresses(addresses: Vec<SocketAddr>, policy: AddressPolicy) -> Result<AddressReport, AddressProbeError> {
+    let attempts = addresses.into_iter()
+        .map(|address| async move {
+            (address, TcpStream::connect(address).await.map(drop))
+        });
+
+    let mut report = AddressReport {
+        reachable: Vec::new(),
+        unreachable: Vec::new()
+    };
+
+    for (address, result) in join_all(attempts).await {
+        match result {
+            Ok(()) => report.reachable.push(address),
+            Err(error) => {
+                debug!("Failed to connect to {}: {}", address, error);
+                report.unreachable.push((address, error));
+            }
+        }
+    }
+
+    if report.satisfies(policy) {
+        Ok(report)
+    }
+    else {
+        Err(AddressProbeError::Unreachable(report))
+    }
+}
+
+/// Probe a server by connecting to every address its host name resolves to,
+/// returning which were reachable.
+pub struct TcpAddressesProbe {
+    host: String,
+    port: u16,
+    policy: AddressPolicy
+}
+
+impl TcpAddressesProbe {
+    pub fn new<H: Into<String>>(host: H, port: u16, policy: AddressPolicy) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            policy
+        }
+    }
+}
+
+impl Probe for TcpAddressesProbe {
+    type Output = AddressReport;
+    type Error = AddressProbeError;
+
+    fn probe(&self) -> ProbeFuture<'_, AddressReport, AddressProbeError> {
+        Box::pin(connect_tcp_server_addresses(&self.host, self.port, self.policy))
+    }
+}
+
+/// Wait until the addresses a host name resolves to accept connections,
+/// according to a policy.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::{wait_for_tcp_server_addresses, AddressPolicy, AddressProbeError, WaitError};
+///
+/// async fn example() -> Result<(), WaitError<AddressProbeError>> {
+///     let backoff = Limit::new(10, Constant::new(Duration::from_secs(1)));
+///
+///     // Listening on both 127.0.0.1 and ::1
+///     let report = wait_for_tcp_server_addresses("localhost", 8080, AddressPolicy::All, backoff).await?;
+///
+///     println!("Reachable: {:?}", report.reachable);
+///     Ok(())
+/// }
+/// ```
+pub async fn wait_for_tcp_server_addresses<B: Backoff>(host: &str, port: u16, policy: AddressPolicy, backoff: B) -> Result<AddressReport, WaitError<AddressProbeError>> {
+    wait_for_tcp_server_addresses_with_options(host, port, policy, WaitOptions::new(backoff)).await
+}
+
+pub async fn wait_for_tcp_server_addresses_with_options<B: Backoff>(host: &str, port: u16, policy: AddressPolicy, options: WaitOptions<B>) -> Result<AddressReport, WaitError<AddressProbeError>> {
+    info!("Will wait until can connect to {:?} of the addresses of {}:{}", policy, host, port);
+
+    wait_for_with_options(TcpAddressesProbe::new(host, port, policy), options).await
+}
+
+#[cfg(test)]
+mod test_connect_addresses {
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+    use tokio::net::TcpListener;
+    use crate::waiter::AddressProbeError;
+    use super::{connect_addresses, connect_tcp_server_addresses, AddressPolicy};
+
+    /// An address that accepts connections, and one on IPv6 at the same port that does not.
+    async fn mixed() -> (TcpListener, SocketAddr, SocketAddr) {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .unwrap();
+
+        let open = listener.local_addr().unwrap();
+        let closed = SocketAddr::new(Ipv6Addr::LOCALHOST.into(), open.port());
+
+        (listener, open, closed)
+    }
+
+    #[tokio::test]
+    async fn all_fails_when_any_unreachable() {
+        let (_listener, open, closed) = mixed().await;
+
+        let actual = connect_addresses(vec![open, closed], AddressPolicy::All)
+            .await
+            .unwrap_err();
+
+        if let AddressProbeError::Unreachable(report) = actual {
+            assert_eq!(vec![open], report.reachable);
+            assert_eq!(vec![closed], report.unreachable.iter().map(|(address, _)| *address).collect::<Vec<_>>());
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+    }
+
+    #[tokio::test]
+    async fn any_succeeds_when_one_reachable() {
+        let (_listener, open, closed) = mixed().await;
+
+        let actual = connect_addresses(vec![closed, open], AddressPolicy::Any)
+            .await
+            .unwrap();
+
+        assert_eq!(vec![open], actual.reachable);
+        assert_eq!(1, actual.unreachable.len());
+    }
+
+    #[tokio::test]
+    async fn resolves_host() {
+        let (_listener, open, _) = mixed().await;
+
+        let actual = connect_tcp_server_addresses("127.0.0.1", open.port(), AddressPolicy::All)
+            .await
+            .unwrap();
+
+        assert_eq!(vec![open], actual.reachable);
+    }
+}
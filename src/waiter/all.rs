@@ -0,0 +1,322 @@
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+use backoff::backoff::Backoff;
+use futures_util::future::join_all;
+use log::{info, warn};
+
+use super::{wait_for_with_options, Probe, ProbeFuture, WaitError, WaitOptions};
+
+/// A named probe, with its own backoff policy, to be waited on by [`wait_for_all`].
+pub struct Service<'a> {
+    name: String,
+    wait: Pin<Box<dyn Future<Output = ServiceReport> + 'a>>
+}
+
+impl<'a> Service<'a> {
+    pub fn new<N, P, B>(name: N, probe: P, backoff: B) -> Self
+    where
+        N: Into<String>,
+        P: Probe + 'a,
+        B: Backoff + 'a
+    {
+        Self::with_options(name, probe, WaitOptions::new(backoff))
+    }
+
+    pub fn with_options<N, P, B>(name: N, probe: P, options: WaitOptions<B>) -> Self
+    where
+        N: Into<String>,
+        P: Probe + 'a,
+        B: Backoff + 'a
+    {
+        let name = name.into();
+        let report_name = name.clone();
+
+        let wait = async move {
+            let counted = Counted::new(probe);
+            let started = Instant::now();
+
+            let status = match wait_for_with_options(&counted, options).await {
+                Ok(_) => ServiceStatus::Ready,
+                Err(WaitError::Failed { .. }) => ServiceStatus::Failed,
+                Err(WaitError::Permanent { .. }) => ServiceStatus::Permanent,
+                Err(WaitError::TimedOut { .. }) => ServiceStatus::TimedOut,
+                Err(WaitError::Unstable { .. }) => ServiceStatus::Unstable,
+                Err(WaitError::Cancelled { .. }) => ServiceStatus::Cancelled
+            };
+
+            ServiceReport {
+                name: report_name,
+                status,
+                attempts: counted.attempts.get(),
+                elapsed: started.elapsed(),
+                last_error: counted.last_error.into_inner()
+            }
+        };
+
+        Self {
+            name,
+            wait: Box::pin(wait)
+        }
+    }
+}
+
+/// Whether a [`Service`] became ready, and if not, which kind of [`WaitError`] ended the wait.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ServiceStatus {
+    Ready,
+
+    /// The backoff policy gave up.
+    Failed,
+
+    /// An attempt failed with an error that is not worth retrying.
+    Permanent,
+
+    /// The overall deadline passed, or the last attempt timed out.
+    TimedOut,
+
+    /// Not enough attempts in a row succeeded.
+    Unstable,
+
+    /// Cancellation was signalled.
+    Cancelled
+}
+
+/// Outcome of waiting on a single [`Service`].
+#[derive(Clone, Debug)]
+pub struct ServiceReport {
+    pub name: String,
+    pub status: ServiceStatus,
+    pub attempts: usize,
+    pub elapsed: Duration,
+
+    /// Error from the most recent failed attempt, if any attempt failed.
+    pub last_error: Option<String>
+}
+
+impl ServiceReport {
+    pub fn is_ready(&self) -> bool {
+        self.status == ServiceStatus::Ready
+    }
+}
+
+/// Outcome of [`wait_for_all`], with one report per service, in the order given.
+#[derive(Clone, Debug)]
+pub struct WaitAllReport {
+    pub services: Vec<ServiceReport>
+}
+
+impl WaitAllReport {
+    pub fn all_ready(&self) -> bool {
+        self.services.iter().all(ServiceReport::is_ready)
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &ServiceReport> {
+        self.services.iter().filter(|service| !service.is_ready())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ServiceReport> {
+        self.services.iter().find(|service| service.name == name)
+    }
+}
+
+/// Wait for several services concurrently.
+///
+/// Every service is waited on until it is ready or its own backoff policy gives
+/// up; a failure of one service does not stop the wait on the others.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use passivized_test_support::http_status_tests::is_success;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::{wait_for_all, HttpProbe, Service, TcpProbe};
+///
+/// async fn example() -> Result<(), String> {
+///     let interval = Duration::from_secs(2);
+///
+///     let report = wait_for_all(vec![
+///         Service::new("db", TcpProbe::new("localhost", 5432), Limit::new(10, Constant::new(interval))),
+///         Service::new("api", HttpProbe::new("http://localhost:8080/health", is_success()), Limit::new(5, Constant::new(interval))),
+///     ]).await;
+///
+///     if report.all_ready() {
+///         Ok(())
+///     }
+///     else {
+///         Err(format!("Services not ready: {:?}", report.failed().collect::<Vec<_>>()))
+///     }
+/// }
+/// ```
+pub async fn wait_for_all<'a, S>(services: S) -> WaitAllReport
+where
+    S: IntoIterator<Item = Service<'a>>
+{
+    let waits = services
+        .into_iter()
+        .map(|service| {
+            info!("Will wait until service {} is ready", service.name);
+            service.wait
+        });
+
+    let services = join_all(waits).await;
+
+    for service in &services {
+        match service.status {
+            ServiceStatus::Ready => info!("Service {} ready after {} attempts", service.name, service.attempts),
+            status => warn!("Service {} not ready ({:?}) after {} attempts: {:?}", service.name, status, service.attempts, service.last_error)
+        }
+    }
+
+    WaitAllReport {
+        services
+    }
+}
+
+/// Counts attempts of an inner probe, and remembers the most recent error.
+struct Counted<P> {
+    inner: P,
+    attempts: Cell<usize>,
+    last_error: RefCell<Option<String>>
+}
+
+impl<P> Counted<P> {
+    fn new(inner: P) -> Self {
+        Self {
+            inner,
+            attempts: Cell::new(0),
+            last_error: RefCell::new(None)
+        }
+    }
+}
+
+impl<P: Probe> Probe for Counted<P> {
+    type Output = P::Output;
+    type Error = P::Error;
+
+    fn probe(&self) -> ProbeFuture<'_, P::Output, P::Error> {
+        self.attempts.set(self.attempts.get() + 1);
+
+        Box::pin(async move {
+            let result = self.inner.probe().await;
+
+            if let Err(error) = &result {
+                *self.last_error.borrow_mut() = Some(format!("{:?}", error));
+            }
+
+            result
+        })
+    }
+
+    fn is_permanent(&self, error: &P::Error) -> bool {
+        self.inner.is_permanent(error)
+    }
+}
+
+#[cfg(test)]
+mod test_wait_for_all {
+    use std::sync::Arc;
+    use std::time::Duration;
+    use backoff::backoff::Constant;
+    use tokio::net::TcpListener;
+    use tokio::sync::Barrier;
+    use crate::retry::Limit;
+    use crate::waiter::{Probe, ProbeFuture, TcpProbe, WaitOptions};
+    use super::{wait_for_all, Service, ServiceStatus};
+
+    /// Ready only while another service's probe is waiting at the same barrier.
+    struct Rendezvous(Arc<Barrier>);
+
+    impl Probe for Rendezvous {
+        type Output = ();
+        type Error = ();
+
+        fn probe(&self) -> ProbeFuture<'_, (), ()> {
+            Box::pin(async move {
+                self.0.wait().await;
+                Ok(())
+            })
+        }
+    }
+
+    async fn closed_port() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap();
+
+        listener.local_addr()
+            .unwrap()
+            .port()
+    }
+
+    fn backoff() -> Limit {
+        Limit::new(4, Constant::new(Duration::from_millis(250)))
+    }
+
+    #[tokio::test]
+    async fn reports_each_service() {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap();
+
+        let open = listener.local_addr()
+            .unwrap()
+            .port();
+
+        let closed = closed_port().await;
+
+        let report = wait_for_all(vec![
+            Service::new("open", TcpProbe::new("127.0.0.1", open), backoff()),
+            Service::new("closed", TcpProbe::new("127.0.0.1", closed), backoff()),
+        ]).await;
+
+        assert!(!report.all_ready());
+        assert_eq!(vec!["open", "closed"], report.services.iter().map(|s| s.name.as_str()).collect::<Vec<_>>());
+
+        let open = report.get("open").unwrap();
+        assert_eq!(ServiceStatus::Ready, open.status);
+        assert_eq!(1, open.attempts);
+        assert_eq!(None, open.last_error);
+
+        let closed = report.get("closed").unwrap();
+        assert_eq!(ServiceStatus::Failed, closed.status);
+        assert_eq!(5, closed.attempts);
+        assert!(closed.last_error.is_some());
+
+        assert_eq!(vec!["closed"], report.failed().map(|s| s.name.as_str()).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn reports_kind_of_failure() {
+        let closed = closed_port().await;
+
+        let options = WaitOptions::new(backoff())
+            .with_deadline(Duration::from_millis(100));
+
+        let report = wait_for_all(vec![
+            Service::with_options("slow", TcpProbe::new("127.0.0.1", closed), options),
+        ]).await;
+
+        assert_eq!(ServiceStatus::TimedOut, report.get("slow").unwrap().status);
+    }
+
+    #[tokio::test]
+    async fn waits_concurrently() {
+        let barrier = Arc::new(Barrier::new(2));
+
+        // Waiting serially, the first probe would never get past the barrier.
+        let options = || WaitOptions::new(Limit::new(1, Constant::new(Duration::ZERO)))
+            .with_attempt_timeout(Duration::from_secs(5));
+
+        let report = wait_for_all(vec![
+            Service::with_options("first", Rendezvous(barrier.clone()), options()),
+            Service::with_options("second", Rendezvous(barrier), options()),
+        ]).await;
+
+        assert!(report.all_ready(), "{:?}", report);
+        assert!(report.services.iter().all(|service| service.attempts == 1));
+    }
+}
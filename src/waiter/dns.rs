@@ -0,0 +1,204 @@
+use std::net::{IpAddr, SocketAddr};
+use backoff::backoff::Backoff;
+use log::info;
+use tokio::net::lookup_host;
+
+use super::errors::{DnsProbeError, UnresolvedHost};
+use super::{wait_for_with_options, Probe, ProbeFuture, WaitError, WaitOptions};
+
+/// Resolve a host name and port to socket addresses, reporting failure as
+/// [`UnresolvedHost`], so that it can be told apart from a refused connection.
+pub(super) async fn resolve(host: &str, port: u16) -> Result<Vec<SocketAddr>, std::io::Error> {
+    let unresolved = |source: Option<std::io::Error>| std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        UnresolvedHost {
+            host: host.to_string(),
+            source
+        }
+    );
+
+    let addresses: Vec<SocketAddr> = lookup_host((host, port))
+        .await
+        .map_err(|error| unresolved(Some(error)))?
+        .collect();
+
+    if addresses.is_empty() {
+        Err(unresolved(None))
+    }
+    else {
+        Ok(addresses)
+    }
+}
+
+/// Probe whether a host name resolves, returning its addresses.
+///
+/// Optionally, the name must resolve to a minimum number of addresses, or
+/// include a specific address.
+pub struct DnsProbe {
+    host: String,
+    min_addresses: usize,
+    required: Vec<IpAddr>
+}
+
+impl DnsProbe {
+    pub fn new<H: Into<String>>(host: H) -> Self {
+        Self {
+            host: host.into(),
+            min_addresses: 1,
+            required: Vec::new()
+        }
+    }
+
+    /// Require the name to resolve to at least this many distinct addresses.
+    pub fn with_min_addresses(mut self, min_addresses: usize) -> Self {
+        self.min_addresses = min_addresses;
+        self
+    }
+
+    /// Require the name to resolve to an address, among any others.
+    pub fn with_address<A: Into<IpAddr>>(mut self, address: A) -> Self {
+        self.required.push(address.into());
+        self
+    }
+
+    async fn lookup(&self) -> Result<Vec<IpAddr>, DnsProbeError> {
+        let mut found: Vec<IpAddr> = Vec::new();
+
+        for address in resolve(&self.host, 0).await.map_err(DnsProbeError::Unresolved)? {
+            if !found.contains(&address.ip()) {
+                found.push(address.ip());
+            }
+        }
+
+        if found.len() < self.min_addresses {
+            return Err(DnsProbeError::TooFewAddresses {
+                found,
+                expected: self.min_addresses
+            });
+        }
+
+        if let Some(missing) = self.required.iter().find(|address| !found.contains(address)) {
+            return Err(DnsProbeError::MissingAddress {
+                expected: *missing,
+                found
+            });
+        }
+
+        Ok(found)
+    }
+}
+
+impl Probe for DnsProbe {
+    type Output = Vec<IpAddr>;
+    type Error = DnsProbeError;
+
+    fn probe(&self) -> ProbeFuture<'_, Vec<IpAddr>, DnsProbeError> {
+        Box::pin(self.lookup())
+    }
+}
+
+/// Wait until a host name resolves, returning its addresses.
+///
+/// To require particular addresses, use a [`DnsProbe`] with [`wait_for`](super::wait_for).
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::{wait_for_dns, wait_for_tcp_server_with_backoff};
+///
+/// async fn example() -> Result<(), Box<dyn std::error::Error>> {
+///     let interval = Duration::from_secs(1);
+///
+///     // A container's name resolves once it has joined the network
+///     wait_for_dns("db", Limit::new(10, Constant::new(interval))).await?;
+///     wait_for_tcp_server_with_backoff("db", 5432, Limit::new(10, Constant::new(interval))).await?;
+///     Ok(())
+/// }
+/// ```
+pub async fn wait_for_dns<B: Backoff>(host: &str, backoff: B) -> Result<Vec<IpAddr>, WaitError<DnsProbeError>> {
+    wait_for_dns_with_options(host, WaitOptions::new(backoff)).await
+}
+
+pub async fn wait_for_dns_with_options<B: Backoff>(host: &str, options: WaitOptions<B>) -> Result<Vec<IpAddr>, WaitError<DnsProbeError>> {
+    info!("Will wait until {} resolves", host);
+
+    wait_for_with_options(DnsProbe::new(host), options).await
+}
+
+#[cfg(test)]
+mod test_dns {
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::Duration;
+    use backoff::backoff::Constant;
+    use crate::retry::Limit;
+    use crate::waiter::{connect_tcp_server, DnsProbeError, Probe, UnresolvedHost, WaitError};
+    use super::{wait_for_dns, DnsProbe};
+
+    const UNRESOLVABLE: &str = "passivized-no-such-host.invalid";
+
+    fn backoff() -> Limit {
+        Limit::new(2, Constant::new(Duration::from_millis(10)))
+    }
+
+    #[tokio::test]
+    async fn connect_reports_unresolved_host() {
+        let actual = connect_tcp_server(UNRESOLVABLE, 80)
+            .await
+            .unwrap_err();
+
+        let unresolved = UnresolvedHost::from_io(&actual).unwrap();
+
+        assert_eq!(UNRESOLVABLE, unresolved.host);
+    }
+
+    #[tokio::test]
+    async fn missing_address() {
+        let actual = DnsProbe::new("localhost")
+            .with_address(Ipv4Addr::new(10, 1, 2, 3))
+            .probe()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual, DnsProbeError::MissingAddress { .. }), "{:?}", actual);
+    }
+
+    #[tokio::test]
+    async fn resolves() {
+        let actual = DnsProbe::new("localhost")
+            .with_address(Ipv4Addr::LOCALHOST)
+            .probe()
+            .await
+            .unwrap();
+
+        assert!(actual.contains(&IpAddr::V4(Ipv4Addr::LOCALHOST)));
+    }
+
+    #[tokio::test]
+    async fn too_few_addresses() {
+        let actual = DnsProbe::new("127.0.0.1")
+            .with_min_addresses(2)
+            .probe()
+            .await
+            .unwrap_err();
+
+        if let DnsProbeError::TooFewAddresses { found, expected } = actual {
+            assert_eq!(vec![IpAddr::V4(Ipv4Addr::LOCALHOST)], found);
+            assert_eq!(2, expected);
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+    }
+
+    #[tokio::test]
+    async fn unresolved() {
+        let actual = wait_for_dns(UNRESOLVABLE, backoff())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual, WaitError::Failed { attempts: 3, error: DnsProbeError::Unresolved(_), .. }), "{:?}", actual);
+    }
+}
@@ -0,0 +1,414 @@
+use std::io::ErrorKind;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::http_errors::HttpError;
+use crate::tls_certificate_tests::PeerCertificate;
+use super::addresses::AddressReport;
+use super::grpc::ServingStatus;
+use super::report::Attempt;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WaitError<E> {
+    /// The backoff policy gave up; contains the error from the last attempt.
+    #[error("gave up after {attempts} attempts in {:.1} seconds: {error:?}", .elapsed.as_secs_f64())]
+    Failed {
+        attempts: usize,
+        elapsed: Duration,
+        error: E,
+
+        /// Every attempt made, for diagnosing why the wait failed.
+        attempt_log: Vec<Attempt>
+    },
+
+    /// An attempt failed with an error that is not worth retrying.
+    #[error("permanent failure after {attempts} attempts in {:.1} seconds: {error:?}", .elapsed.as_secs_f64())]
+    Permanent {
+        attempts: usize,
+        elapsed: Duration,
+        error: E,
+
+        /// Every attempt made, for diagnosing why the wait failed.
+        attempt_log: Vec<Attempt>
+    },
+
+    /// The overall deadline passed, or the last attempt was aborted by the
+    /// per-attempt timeout.
+    #[error("timed out after {attempts} attempts in {:.1} seconds", .elapsed.as_secs_f64())]
+    TimedOut {
+        attempts: usize,
+        elapsed: Duration,
+
+        /// Error from the most recent attempt that failed without timing out.
+        last_error: Option<E>,
+
+        /// Every attempt made, for diagnosing why the wait failed.
+        attempt_log: Vec<Attempt>
+    },
+
+    /// At least one attempt succeeded, but not enough in a row to be considered
+    /// stable before the backoff policy gave up or the deadline passed.
+    #[error("not stable after {attempts} attempts in {:.1} seconds: {history:?}", .elapsed.as_secs_f64())]
+    Unstable {
+        attempts: usize,
+        elapsed: Duration,
+        history: Vec<AttemptOutcome>,
+
+        /// Error from the most recent attempt that failed without timing out.
+        last_error: Option<E>,
+
+        /// Every attempt made, for diagnosing why the wait failed.
+        attempt_log: Vec<Attempt>
+    },
+
+    /// Cancellation was signalled before the wait completed.
+    #[error("cancelled after {attempts} attempts in {:.1} seconds", .elapsed.as_secs_f64())]
+    Cancelled {
+        attempts: usize,
+        elapsed: Duration,
+        history: Vec<AttemptOutcome>,
+
+        /// Error from the most recent attempt that failed without timing out.
+        last_error: Option<E>,
+
+        /// Every attempt made, for diagnosing why the wait failed.
+        attempt_log: Vec<Attempt>
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AttemptOutcome {
+    Succeeded,
+    Failed,
+    TimedOut,
+    Cancelled
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LogWaitError {
+    #[error("output closed before an expected line; last lines: {last_lines:?}")]
+    Closed {
+        last_lines: Vec<String>
+    },
+
+    #[error("failed to read output: {error}; last lines: {last_lines:?}")]
+    Io {
+        error: std::io::Error,
+        last_lines: Vec<String>
+    },
+
+    #[error("child process has neither stdout nor stderr piped")]
+    NotPiped,
+
+    #[error("timed out after {:.1} seconds waiting for an expected line; last lines: {last_lines:?}", .elapsed.as_secs_f64())]
+    TimedOut {
+        elapsed: Duration,
+        last_lines: Vec<String>
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MetricProbeError {
+    #[error("{0}")]
+    Http(HttpError),
+
+    #[error("metric not found: {0}")]
+    Missing(String),
+
+    #[error("{0}")]
+    Parse(String),
+
+    #[error("{metric} is {observed}, expected {expected}")]
+    Unsatisfied {
+        metric: String,
+        observed: f64,
+        expected: String
+    }
+}
+
+/// A host name did not resolve, as distinct from a connection being refused.
+///
+/// Connecting functions that return [`std::io::Error`] carry this as the inner error.
+#[derive(Debug, thiserror::Error)]
+#[error("failed to resolve {host}")]
+pub struct UnresolvedHost {
+    pub host: String,
+
+    /// Error from the resolver, if it failed rather than finding no addresses.
+    #[source]
+    pub source: Option<std::io::Error>
+}
+
+impl UnresolvedHost {
+    /// The resolution failure within an I/O error, if that is what it is.
+    pub fn from_io(error: &std::io::Error) -> Option<&Self> {
+        error.get_ref()?.downcast_ref()
+    }
+}
+
+/// A server that was expected to be down accepted a connection.
+#[derive(Debug, thiserror::Error)]
+#[error("{target} is still accepting connections")]
+pub struct StillUp {
+    pub target: String
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AddressProbeError {
+    #[error("{0}")]
+    Unresolved(std::io::Error),
+
+    #[error("reachable addresses {:?} did not satisfy the policy; unreachable: {:?}", .0.reachable, .0.unreachable)]
+    Unreachable(AddressReport)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DnsProbeError {
+    #[error("{0}")]
+    Unresolved(std::io::Error),
+
+    #[error("resolved to {found:?}, expected at least {expected} addresses")]
+    TooFewAddresses {
+        found: Vec<IpAddr>,
+        expected: usize
+    },
+
+    #[error("resolved to {found:?}, expected {expected}")]
+    MissingAddress {
+        found: Vec<IpAddr>,
+        expected: IpAddr
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExecProbeError {
+    #[error("failed to run command: {0}")]
+    Spawn(std::io::Error),
+
+    #[error("command failed with {status}: {stderr}")]
+    Failed {
+        status: std::process::ExitStatus,
+        stderr: String
+    }
+}
+
+/// State of a file that did not yet satisfy a file waiter.
+#[derive(Debug, thiserror::Error)]
+pub enum FileProbeError {
+    #[error("{} does not exist", .0.display())]
+    Missing(std::path::PathBuf),
+
+    #[error("file is empty")]
+    Empty,
+
+    #[error("{0}")]
+    Io(std::io::Error),
+
+    /// Contains the start of the file contents.
+    #[error("contents did not match: {0:?}")]
+    Unmatched(String),
+
+    /// Contains the start of the file contents.
+    #[error("contents are not valid JSON ({error}): {contents:?}")]
+    InvalidJson {
+        error: String,
+        contents: String
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GrpcProbeError {
+    #[error("{0}")]
+    Http(HttpError),
+
+    #[error("invalid response: {0}")]
+    InvalidResponse(String),
+
+    /// The call failed with a gRPC status other than OK.
+    #[error("gRPC status {code}: {message}")]
+    Status {
+        code: u32,
+        message: String
+    },
+
+    #[error("health status is {0:?}")]
+    NotServing(ServingStatus)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TlsProbeError {
+    #[error("failed to connect: {0}")]
+    Connect(std::io::Error),
+
+    #[error("TLS handshake failed: {0}")]
+    Handshake(crate::tls::TlsError),
+
+    #[error("server did not present a certificate")]
+    NoCertificate,
+
+    #[error("invalid certificate: {0}")]
+    InvalidCertificate(String),
+
+    #[error("certificate did not pass tests: {0:?}")]
+    UnexpectedCertificate(PeerCertificate)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WireProbeError {
+    #[error("connection closed before the expected response")]
+    Closed,
+
+    #[error("{0}")]
+    Io(std::io::Error),
+
+    /// The server responded, and said it is not ready yet.
+    #[error("not ready: {0}")]
+    NotReady(String),
+
+    #[error("unexpected response: {0}")]
+    Unexpected(String)
+}
+
+impl From<std::io::Error> for WireProbeError {
+    fn from(other: std::io::Error) -> Self {
+        Self::Io(other)
+    }
+}
+
+/// Failure of a probe parsed from a [`ProbeSpec`](super::ProbeSpec).
+#[derive(Debug, thiserror::Error)]
+pub enum SpecProbeError {
+    #[error("{0}")]
+    Exec(ExecProbeError),
+
+    #[error("{0}")]
+    Http(HttpError),
+
+    #[error("{0}")]
+    Io(std::io::Error),
+
+    #[error("{0}")]
+    Tls(crate::tls::TlsError),
+
+    #[error("{0}")]
+    Unsupported(String)
+}
+
+impl From<ExecProbeError> for SpecProbeError {
+    fn from(other: ExecProbeError) -> Self {
+        Self::Exec(other)
+    }
+}
+
+impl From<HttpError> for SpecProbeError {
+    fn from(other: HttpError) -> Self {
+        Self::Http(other)
+    }
+}
+
+impl From<std::io::Error> for SpecProbeError {
+    fn from(other: std::io::Error) -> Self {
+        Self::Io(other)
+    }
+}
+
+impl<E> WaitError<E> {
+
+    pub fn attempts(&self) -> usize {
+        match self {
+            Self::Failed { attempts, .. } => *attempts,
+            Self::Permanent { attempts, .. } => *attempts,
+            Self::TimedOut { attempts, .. } => *attempts,
+            Self::Unstable { attempts, .. } => *attempts,
+            Self::Cancelled { attempts, .. } => *attempts
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        match self {
+            Self::Failed { elapsed, .. } => *elapsed,
+            Self::Permanent { elapsed, .. } => *elapsed,
+            Self::TimedOut { elapsed, .. } => *elapsed,
+            Self::Unstable { elapsed, .. } => *elapsed,
+            Self::Cancelled { elapsed, .. } => *elapsed
+        }
+    }
+
+    pub fn attempt_log(&self) -> &[Attempt] {
+        match self {
+            Self::Failed { attempt_log, .. } => attempt_log,
+            Self::Permanent { attempt_log, .. } => attempt_log,
+            Self::TimedOut { attempt_log, .. } => attempt_log,
+            Self::Unstable { attempt_log, .. } => attempt_log,
+            Self::Cancelled { attempt_log, .. } => attempt_log
+        }
+    }
+
+}
+
+impl From<WaitError<HttpError>> for HttpError {
+    fn from(other: WaitError<HttpError>) -> Self {
+        match other {
+            WaitError::Failed { error, .. } => error,
+            WaitError::Permanent { error, .. } => error,
+            other => Self::Other(Box::new(other))
+        }
+    }
+}
+
+impl From<WaitError<std::io::Error>> for std::io::Error {
+    fn from(other: WaitError<std::io::Error>) -> Self {
+        match other {
+            WaitError::Failed { error, .. } => error,
+            WaitError::Permanent { error, .. } => error,
+            other => Self::new(ErrorKind::TimedOut, other)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_wait_error {
+    use std::io::ErrorKind;
+    use std::time::Duration;
+    use super::WaitError;
+
+    #[test]
+    fn displays_timeout() {
+        let error: WaitError<String> = WaitError::TimedOut {
+            attempts: 3,
+            elapsed: Duration::from_millis(7400),
+            last_error: None,
+            attempt_log: Vec::new()
+        };
+
+        assert_eq!("timed out after 3 attempts in 7.4 seconds", error.to_string());
+    }
+
+    #[test]
+    fn unwraps_failed_into_io_error() {
+        let error: WaitError<std::io::Error> = WaitError::Failed {
+            attempts: 2,
+            elapsed: Duration::from_secs(2),
+            error: std::io::Error::from(ErrorKind::ConnectionRefused),
+            attempt_log: Vec::new()
+        };
+
+        let actual: std::io::Error = error.into();
+
+        assert_eq!(ErrorKind::ConnectionRefused, actual.kind());
+    }
+
+    #[test]
+    fn converts_timeout_into_io_error() {
+        let error: WaitError<std::io::Error> = WaitError::TimedOut {
+            attempts: 2,
+            elapsed: Duration::from_secs(2),
+            last_error: None,
+            attempt_log: Vec::new()
+        };
+
+        let actual: std::io::Error = error.into();
+
+        assert_eq!(ErrorKind::TimedOut, actual.kind());
+    }
+}
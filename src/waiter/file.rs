@@ -0,0 +1,372 @@
+use std::path::{Path, PathBuf};
+use backoff::backoff::Backoff;
+use log::info;
+use regex::Regex;
+
+use crate::snippets::snippet;
+use super::errors::FileProbeError;
+use super::{wait_for_with_options, Probe, ProbeFuture, WaitError, WaitOptions};
+
+/// What the contents of a file must be, for a [`FileProbe`].
+#[derive(Clone, Debug)]
+pub enum FileCondition {
+    NonEmpty,
+    Matches(Regex),
+
+    /// The contents parse as JSON, such as a complete generated configuration.
+    #[cfg(feature = "json")]
+    Json
+}
+
+/// Probe whether a path exists, whether it is a file, directory, or socket.
+pub struct PathProbe {
+    path: PathBuf
+}
+
+impl PathProbe {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into()
+        }
+    }
+
+    async fn check(&self) -> Result<(), FileProbeError> {
+        tokio::fs::metadata(&self.path)
+            .await
+            .map(drop)
+            .map_err(|error| missing_or_io(&self.path, error))
+    }
+}
+
+impl Probe for PathProbe {
+    type Output = ();
+    type Error = FileProbeError;
+
+    fn probe(&self) -> ProbeFuture<'_, (), FileProbeError> {
+        Box::pin(self.check())
+    }
+}
+
+/// Probe whether a file has contents satisfying a condition, returning the raw
+/// contents. Only conditions on text require the contents to be UTF-8.
+///
+/// Failures carry what was observed, so that a wait that gives up reports the
+/// last state of the file.
+pub struct FileProbe<T = Vec<u8>> {
+    path: PathBuf,
+    condition: FileCondition,
+
+    /// Turns contents satisfying the condition into the output, for the waiters
+    /// that return text or JSON.
+    output: fn(Vec<u8>) -> Result<T, FileProbeError>
+}
+
+impl FileProbe {
+    pub fn new<P: Into<PathBuf>>(path: P, condition: FileCondition) -> Self {
+        FileProbe::with_output(path, condition, Ok)
+    }
+}
+
+impl<T> FileProbe<T> {
+    fn with_output<P: Into<PathBuf>>(path: P, condition: FileCondition, output: fn(Vec<u8>) -> Result<T, FileProbeError>) -> Self {
+        Self {
+            path: path.into(),
+            condition,
+            output
+        }
+    }
+
+    async fn check(&self) -> Result<T, FileProbeError> {
+        let contents = read(&self.path).await?;
+
+        match &self.condition {
+            FileCondition::NonEmpty => {
+                non_empty(&contents)?;
+            }
+            FileCondition::Matches(pattern) => {
+                matching(&contents, pattern)?;
+            }
+            #[cfg(feature = "json")]
+            FileCondition::Json => {
+                json(&contents)?;
+            }
+        }
+
+        (self.output)(contents)
+    }
+}
+
+impl<T> Probe for FileProbe<T> {
+    type Output = T;
+    type Error = FileProbeError;
+
+    fn probe(&self) -> ProbeFuture<'_, T, FileProbeError> {
+        Box::pin(self.check())
+    }
+}
+
+async fn read(path: &Path) -> Result<Vec<u8>, FileProbeError> {
+    tokio::fs::read(path)
+        .await
+        .map_err(|error| missing_or_io(path, error))
+}
+
+fn non_empty(contents: &[u8]) -> Result<(), FileProbeError> {
+    if contents.is_empty() {
+        Err(FileProbeError::Empty)
+    }
+    else {
+        Ok(())
+    }
+}
+
+fn matching(contents: &[u8], pattern: &Regex) -> Result<(), FileProbeError> {
+    match std::str::from_utf8(contents) {
+        Ok(text) if pattern.is_match(text) => Ok(()),
+        _ => Err(FileProbeError::Unmatched(snippet(contents)))
+    }
+}
+
+fn text(contents: Vec<u8>) -> Result<String, FileProbeError> {
+    String::from_utf8(contents)
+        .map_err(|error| FileProbeError::Unmatched(snippet(error.as_bytes())))
+}
+
+#[cfg(feature = "json")]
+fn json(contents: &[u8]) -> Result<serde_json::Value, FileProbeError> {
+    serde_json::from_slice(contents)
+        .map_err(|error| FileProbeError::InvalidJson {
+            error: error.to_string(),
+            contents: snippet(contents)
+        })
+}
+
+fn missing_or_io(path: &Path, error: std::io::Error) -> FileProbeError {
+    if error.kind() == std::io::ErrorKind::NotFound {
+        FileProbeError::Missing(path.to_path_buf())
+    }
+    else {
+        FileProbeError::Io(error)
+    }
+}
+
+/// Wait until a path exists, such as a PID file or socket.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::{wait_for_path, FileProbeError, WaitError};
+///
+/// async fn example() -> Result<(), WaitError<FileProbeError>> {
+///     let backoff = Limit::new(20, Constant::new(Duration::from_millis(500)));
+///
+///     wait_for_path("/var/run/example/server.pid", backoff).await
+/// }
+/// ```
+pub async fn wait_for_path<P: AsRef<Path>, B: Backoff>(path: P, backoff: B) -> Result<(), WaitError<FileProbeError>> {
+    wait_for_path_with_options(path, WaitOptions::new(backoff)).await
+}
+
+pub async fn wait_for_path_with_options<P: AsRef<Path>, B: Backoff>(path: P, options: WaitOptions<B>) -> Result<(), WaitError<FileProbeError>> {
+    let path = path.as_ref();
+
+    info!("Will wait until {} exists", path.display());
+
+    wait_for_with_options(PathProbe::new(path), options).await
+}
+
+/// Wait until a file is non-empty, returning its contents, which need not be text.
+pub async fn wait_for_file_non_empty<P: AsRef<Path>, B: Backoff>(path: P, backoff: B) -> Result<Vec<u8>, WaitError<FileProbeError>> {
+    wait_for_file_non_empty_with_options(path, WaitOptions::new(backoff)).await
+}
+
+pub async fn wait_for_file_non_empty_with_options<P: AsRef<Path>, B: Backoff>(path: P, options: WaitOptions<B>) -> Result<Vec<u8>, WaitError<FileProbeError>> {
+    let path = path.as_ref();
+
+    info!("Will wait until {} is not empty", path.display());
+
+    wait_for_with_options(FileProbe::new(path, FileCondition::NonEmpty), options).await
+}
+
+/// Wait until the contents of a file match a pattern, returning the contents.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use regex::Regex;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::{wait_for_file_matching, FileProbeError, WaitError};
+///
+/// async fn example() -> Result<(), WaitError<FileProbeError>> {
+///     let backoff = Limit::new(20, Constant::new(Duration::from_millis(500)));
+///     let complete = Regex::new("-----END CERTIFICATE-----").unwrap();
+///
+///     wait_for_file_matching("/shared/server.crt", &complete, backoff).await?;
+///     Ok(())
+/// }
+/// ```
+pub async fn wait_for_file_matching<P: AsRef<Path>, B: Backoff>(path: P, pattern: &Regex, backoff: B) -> Result<String, WaitError<FileProbeError>> {
+    wait_for_file_matching_with_options(path, pattern, WaitOptions::new(backoff)).await
+}
+
+pub async fn wait_for_file_matching_with_options<P: AsRef<Path>, B: Backoff>(path: P, pattern: &Regex, options: WaitOptions<B>) -> Result<String, WaitError<FileProbeError>> {
+    let path = path.as_ref();
+
+    info!("Will wait until {} matches {}", path.display(), pattern);
+
+    wait_for_with_options(FileProbe::with_output(path, FileCondition::Matches(pattern.clone()), text), options).await
+}
+
+/// Wait until the contents of a file parse as JSON, returning the parsed value.
+#[cfg(feature = "json")]
+pub async fn wait_for_json_file<P: AsRef<Path>, B: Backoff>(path: P, backoff: B) -> Result<serde_json::Value, WaitError<FileProbeError>> {
+    wait_for_json_file_with_options(path, WaitOptions::new(backoff)).await
+}
+
+#[cfg(feature = "json")]
+pub async fn wait_for_json_file_with_options<P: AsRef<Path>, B: Backoff>(path: P, options: WaitOptions<B>) -> Result<serde_json::Value, WaitError<FileProbeError>> {
+    let path = path.as_ref();
+
+    info!("Will wait until {} contains JSON", path.display());
+
+    wait_for_with_options(FileProbe::with_output(path, FileCondition::Json, |contents| json(&contents)), options).await
+}
+
+#[cfg(test)]
+mod test_file_waiters {
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+    use backoff::backoff::Constant;
+    use regex::Regex;
+    use crate::retry::Limit;
+    use crate::timestamps::named;
+    use crate::waiter::{FileProbeError, WaitError};
+    use super::{wait_for_file_matching, wait_for_file_non_empty, wait_for_path};
+
+    fn backoff() -> Limit {
+        Limit::new(20, Constant::new(Duration::from_millis(10)))
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("{}-{}", named(name), std::process::id()))
+    }
+
+    /// Write the file after a few attempts have been made.
+    fn write_later(path: &Path, contents: &'static str) {
+        let path = path.to_path_buf();
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            std::fs::write(path, contents).unwrap();
+        });
+    }
+
+    #[tokio::test]
+    async fn reports_last_contents() {
+        let path = temp_path("unmatched");
+        std::fs::write(&path, "starting").unwrap();
+
+        let actual = wait_for_file_matching(&path, &Regex::new("^ready$").unwrap(), Limit::new(2, Constant::new(Duration::from_millis(10))))
+            .await
+            .unwrap_err();
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(actual, WaitError::Failed { error: FileProbeError::Unmatched(ref contents), .. } if contents == "starting"), "{:?}", actual);
+    }
+
+    #[tokio::test]
+    async fn reports_missing() {
+        let path = temp_path("missing");
+
+        let actual = wait_for_path(&path, Limit::new(2, Constant::new(Duration::from_millis(10))))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual, WaitError::Failed { error: FileProbeError::Missing(ref missing), .. } if *missing == path), "{:?}", actual);
+    }
+
+    #[tokio::test]
+    async fn waits_for_match() {
+        let path = temp_path("match");
+        std::fs::write(&path, "starting").unwrap();
+        write_later(&path, "port=8080\n");
+
+        let actual = wait_for_file_matching(&path, &Regex::new(r"port=\d+").unwrap(), backoff())
+            .await
+            .unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!("port=8080\n", actual);
+    }
+
+    #[tokio::test]
+    async fn waits_for_non_empty() {
+        let path = temp_path("non-empty");
+        std::fs::write(&path, "").unwrap();
+        write_later(&path, "1234");
+
+        let actual = wait_for_file_non_empty(&path, backoff())
+            .await
+            .unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(b"1234".to_vec(), actual);
+    }
+
+    #[tokio::test]
+    async fn waits_for_non_empty_binary() {
+        let path = temp_path("binary");
+        let der = vec![0x30, 0x82, 0xff, 0xfe, 0x00];
+        std::fs::write(&path, &der).unwrap();
+
+        let actual = wait_for_file_non_empty(&path, backoff())
+            .await
+            .unwrap();
+
+        let unmatched = wait_for_file_matching(&path, &Regex::new(".").unwrap(), Limit::new(1, Constant::new(Duration::from_millis(10))))
+            .await
+            .unwrap_err();
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(der, actual);
+        assert!(matches!(unmatched, WaitError::Failed { error: FileProbeError::Unmatched(_), .. }), "{:?}", unmatched);
+    }
+
+    #[tokio::test]
+    async fn waits_for_path() {
+        let path = temp_path("exists");
+        write_later(&path, "");
+
+        wait_for_path(&path, backoff())
+            .await
+            .unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn waits_for_json() {
+        let path = temp_path("json");
+        std::fs::write(&path, "{\"partial\": ").unwrap();
+        write_later(&path, "{\"port\": 8080}");
+
+        let actual = super::wait_for_json_file(&path, backoff())
+            .await
+            .unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(serde_json::json!({"port": 8080}), actual);
+    }
+}
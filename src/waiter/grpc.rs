@@ -0,0 +1,389 @@
+use backoff::backoff::Backoff;
+use http::StatusCode;
+use hyper::body::Bytes;
+use log::info;
+
+use super::errors::GrpcProbeError;
+use super::{wait_for_with_options, Probe, ProbeFuture, WaitError, WaitOptions};
+
+const HEALTH_CHECK_PATH: &str = "/grpc.health.v1.Health/Check";
+
+/// gRPC status code for a method the server does not implement.
+const UNIMPLEMENTED: u32 = 12;
+
+/// Serving status from the standard gRPC health checking protocol.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ServingStatus {
+    Unknown,
+    Serving,
+    NotServing,
+    ServiceUnknown
+}
+
+impl From<u64> for ServingStatus {
+    fn from(other: u64) -> Self {
+        match other {
+            1 => Self::Serving,
+            2 => Self::NotServing,
+            3 => Self::ServiceUnknown,
+            _ => Self::Unknown
+        }
+    }
+}
+
+/// Probe a gRPC server, without TLS, by calling `grpc.health.v1.Health/Check`
+/// and expecting `SERVING`.
+///
+/// The service name is empty for the health of the server as a whole.
+///
+/// A server that does not implement the health service fails permanently.
+pub struct GrpcHealthProbe {
+    url: String,
+    service: String
+}
+
+impl GrpcHealthProbe {
+    /// The URL is the root of the server, such as `http://localhost:50051`.
+    pub fn new<U: Into<String>, S: Into<String>>(url: U, service: S) -> Self {
+        Self {
+            url: url.into(),
+            service: service.into()
+        }
+    }
+
+    async fn check(&self) -> Result<(), GrpcProbeError> {
+        let url = format!("{}{}", self.url.trim_end_matches('/'), HEALTH_CHECK_PATH);
+
+        let (head, body) = crate::http::post_grpc(&url, encode_request(&self.service))
+            .await
+            .map_err(GrpcProbeError::Http)?;
+
+        if head.status != StatusCode::OK {
+            return Err(GrpcProbeError::Http(crate::http_errors::HttpError::Status(head.status)));
+        }
+
+        // A response without a message carries its status in the headers instead of trailers.
+        let trailers = body.trailers()
+            .cloned()
+            .unwrap_or(head.headers);
+
+        let code = trailers.get("grpc-status")
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| GrpcProbeError::InvalidResponse("missing grpc-status".into()))?
+            .parse::<u32>()
+            .map_err(|error| GrpcProbeError::InvalidResponse(format!("grpc-status {}", error)))?;
+
+        if code != 0 {
+            let message = trailers.get("grpc-message")
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+                .to_string();
+
+            return Err(GrpcProbeError::Status { code, message });
+        }
+
+        match decode_response(&body.to_bytes())? {
+            ServingStatus::Serving => Ok(()),
+            other => Err(GrpcProbeError::NotServing(other))
+        }
+    }
+}
+
+impl Probe for GrpcHealthProbe {
+    type Output = ();
+    type Error = GrpcProbeError;
+
+    fn probe(&self) -> ProbeFuture<'_, (), GrpcProbeError> {
+        Box::pin(self.check())
+    }
+
+    fn is_permanent(&self, error: &GrpcProbeError) -> bool {
+        matches!(error, GrpcProbeError::Status { code: UNIMPLEMENTED, .. })
+    }
+}
+
+/// Length-prefixed message holding a `HealthCheckRequest`, whose only field is
+/// the service name, field number 1.
+fn encode_request(service: &str) -> Bytes {
+    let mut message = Vec::new();
+
+    if !service.is_empty() {
+        message.push(0x0a);
+        encode_varint(service.len() as u64, &mut message);
+        message.extend_from_slice(service.as_bytes());
+    }
+
+    let mut framed = vec![0u8];
+    framed.extend((message.len() as u32).to_be_bytes());
+    framed.extend(message);
+
+    Bytes::from(framed)
+}
+
+/// Serving status from a length-prefixed `HealthCheckResponse`, whose only field
+/// is the status, field number 1.
+fn decode_response(framed: &[u8]) -> Result<ServingStatus, GrpcProbeError> {
+    let invalid = |reason: &str| GrpcProbeError::InvalidResponse(reason.to_string());
+
+    if framed.len() < 5 {
+        return Err(invalid("truncated message"));
+    }
+
+    if framed[0] != 0 {
+        return Err(invalid("compressed message"));
+    }
+
+    let length = u32::from_be_bytes([framed[1], framed[2], framed[3], framed[4]]) as usize;
+
+    let mut message = framed.get(5..5 + length)
+        .ok_or_else(|| invalid("truncated message"))?;
+
+    // Absent fields have their default value, which is UNKNOWN.
+    let mut status = ServingStatus::Unknown;
+
+    while !message.is_empty() {
+        let key = decode_varint(&mut message).ok_or_else(|| invalid("truncated field"))?;
+
+        match (key >> 3, key & 7) {
+            (1, 0) => {
+                status = decode_varint(&mut message)
+                    .ok_or_else(|| invalid("truncated status"))?
+                    .into();
+            }
+            (_, 0) => {
+                decode_varint(&mut message).ok_or_else(|| invalid("truncated field"))?;
+            }
+            (_, 2) => {
+                let skip = decode_varint(&mut message).ok_or_else(|| invalid("truncated field"))? as usize;
+                message = message.get(skip..).ok_or_else(|| invalid("truncated field"))?;
+            }
+            (_, wire_type) => {
+                return Err(GrpcProbeError::InvalidResponse(format!("unsupported wire type {}", wire_type)));
+            }
+        }
+    }
+
+    Ok(status)
+}
+
+fn encode_varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+
+    out.push(value as u8);
+}
+
+fn decode_varint(input: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = input.split_first()?;
+        *input = rest;
+
+        value |= u64::from(byte & 0x7f) << shift;
+
+        if byte < 0x80 {
+            return Some(value);
+        }
+    }
+
+    None
+}
+
+/// Wait until a gRPC server, not using TLS, reports that a service is serving.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::{wait_for_grpc_health, GrpcProbeError, WaitError};
+///
+/// async fn example() -> Result<(), WaitError<GrpcProbeError>> {
+///     let backoff = Limit::new(10, Constant::new(Duration::from_secs(1)));
+///
+///     // Empty service name for the server as a whole
+///     wait_for_grpc_health("http://localhost:50051", "", backoff).await
+/// }
+/// ```
+pub async fn wait_for_grpc_health<B: Backoff>(url: &str, service: &str, backoff: B) -> Result<(), WaitError<GrpcProbeError>> {
+    wait_for_grpc_health_with_options(url, service, WaitOptions::new(backoff)).await
+}
+
+pub async fn wait_for_grpc_health_with_options<B: Backoff>(url: &str, service: &str, options: WaitOptions<B>) -> Result<(), WaitError<GrpcProbeError>> {
+    info!("Will wait until gRPC service {:?} at {} is serving", service, url);
+
+    wait_for_with_options(GrpcHealthProbe::new(url, service), options).await
+}
+
+#[cfg(test)]
+mod test_grpc_health_probe {
+    use std::convert::Infallible;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use backoff::backoff::Constant;
+    use futures_util::stream;
+    use http::{HeaderMap, HeaderValue, Request, Response};
+    use http_body_util::{BodyExt, StreamBody};
+    use hyper::body::{Bytes, Frame, Incoming};
+    use hyper::service::service_fn;
+    use hyper_util::rt::{TokioExecutor, TokioIo};
+    use tokio::net::TcpListener;
+    use crate::retry::Limit;
+    use crate::waiter::{GrpcProbeError, Probe, WaitError};
+    use super::{decode_response, encode_request, wait_for_grpc_health, GrpcHealthProbe, ServingStatus};
+
+    type CannedBody = StreamBody<stream::Iter<std::vec::IntoIter<Result<Frame<Bytes>, Infallible>>>>;
+
+    /// Canned reply: serving status, if a message is sent, and grpc-status.
+    type Reply = (Option<u8>, u32);
+
+    /// Fake HTTP/2 gRPC server that replies to each request with the next canned
+    /// reply, repeating the last one, and records the service name requested.
+    async fn serve(replies: Vec<Reply>) -> (String, Arc<std::sync::Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap();
+
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requested = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let replies = Arc::new(replies);
+
+        let recorded = requested.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept()
+                    .await
+                    .unwrap();
+
+                let requested = recorded.clone();
+                let calls = calls.clone();
+                let replies = replies.clone();
+
+                let service = service_fn(move |request: Request<Incoming>| {
+                    let requested = requested.clone();
+                    let calls = calls.clone();
+                    let replies = replies.clone();
+
+                    async move {
+                        assert_eq!("/grpc.health.v1.Health/Check", request.uri().path());
+
+                        let body = request.into_body()
+                            .collect()
+                            .await
+                            .unwrap()
+                            .to_bytes();
+
+                        // Skip the frame prefix, field key, and single byte length.
+                        requested.lock().unwrap().push(String::from_utf8_lossy(body.get(7..).unwrap_or_default()).into_owned());
+
+                        let call = calls.fetch_add(1, Ordering::SeqCst);
+                        let (status, code) = replies[call.min(replies.len() - 1)];
+
+                        Ok::<_, Infallible>(canned(status, code))
+                    }
+                });
+
+                tokio::spawn(async move {
+                    let _ = hyper::server::conn::http2::Builder::new(TokioExecutor::new())
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await;
+                });
+            }
+        });
+
+        (url, requested)
+    }
+
+    fn canned(status: Option<u8>, code: u32) -> Response<CannedBody> {
+        let mut frames = Vec::new();
+
+        if let Some(status) = status {
+            frames.push(Ok(Frame::data(Bytes::from(vec![0, 0, 0, 0, 2, 0x08, status]))));
+        }
+
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", HeaderValue::from(code));
+
+        if code != 0 {
+            trailers.insert("grpc-message", HeaderValue::from_static("canned failure"));
+        }
+
+        frames.push(Ok(Frame::trailers(trailers)));
+
+        Response::builder()
+            .header("content-type", "application/grpc")
+            .body(StreamBody::new(stream::iter(frames)))
+            .unwrap()
+    }
+
+    fn backoff() -> Limit {
+        Limit::new(4, Constant::new(Duration::from_millis(10)))
+    }
+
+    #[test]
+    fn decodes_default_status() {
+        assert_eq!(ServingStatus::Unknown, decode_response(&[0, 0, 0, 0, 0]).unwrap());
+    }
+
+    #[test]
+    fn decodes_skipping_unknown_fields() {
+        // Field 2 length-delimited "ab", then field 1 = NOT_SERVING
+        let framed = [0, 0, 0, 0, 6, 0x12, 2, b'a', b'b', 0x08, 2];
+
+        assert_eq!(ServingStatus::NotServing, decode_response(&framed).unwrap());
+    }
+
+    #[test]
+    fn encodes_service_name() {
+        assert_eq!(&[0, 0, 0, 0, 0][..], &encode_request("")[..]);
+        assert_eq!(&[0, 0, 0, 0, 5, 0x0a, 3, b'f', b'o', b'o'][..], &encode_request("foo")[..]);
+    }
+
+    #[tokio::test]
+    async fn not_serving() {
+        let (url, _) = serve(vec![(Some(2), 0)]).await;
+
+        let actual = GrpcHealthProbe::new(url, "")
+            .probe()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual, GrpcProbeError::NotServing(ServingStatus::NotServing)), "{:?}", actual);
+    }
+
+    #[tokio::test]
+    async fn unimplemented_is_permanent() {
+        let (url, _) = serve(vec![(None, 12)]).await;
+
+        let actual = wait_for_grpc_health(&url, "", backoff())
+            .await
+            .unwrap_err();
+
+        if let WaitError::Permanent { attempts, error: GrpcProbeError::Status { code, message }, .. } = actual {
+            assert_eq!(1, attempts);
+            assert_eq!(12, code);
+            assert_eq!("canned failure", message);
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+    }
+
+    #[tokio::test]
+    async fn waits_until_serving() {
+        let (url, requested) = serve(vec![(Some(2), 0), (Some(0), 0), (Some(1), 0)]).await;
+
+        wait_for_grpc_health(&url, "app", backoff())
+            .await
+            .unwrap();
+
+        assert_eq!(vec!["app", "app", "app"], *requested.lock().unwrap());
+    }
+}
@@ -0,0 +1,265 @@
+use std::time::{Duration, Instant};
+use futures_util::future::{select, Either};
+use log::info;
+use regex::Regex;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader, Lines};
+use tokio::process::Child;
+use tokio::time::timeout;
+
+use super::errors::LogWaitError;
+
+/// How many of the most recent lines to include in an error.
+const LAST_LINES: usize = 20;
+
+/// A line of output that matched, and all output read up to and including it.
+#[derive(Clone, Debug)]
+pub struct LogLine {
+    pub line: String,
+    pub captured: Vec<String>
+}
+
+/// Read lines from any source, such as a pipe or a socket, until one matches a pattern.
+///
+/// Output buffered past the matching line is discarded.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use regex::Regex;
+/// use passivized_test_support::waiter::{wait_for_log_line, LogWaitError};
+///
+/// async fn example() -> Result<(), LogWaitError> {
+///     let output: &[u8] = b"starting\nlistening on 127.0.0.1:8080\n";
+///     let pattern = Regex::new("listening on").unwrap();
+///
+///     let found = wait_for_log_line(output, &pattern, Duration::from_secs(10)).await?;
+///
+///     assert_eq!("listening on 127.0.0.1:8080", found.line);
+///     Ok(())
+/// }
+/// ```
+pub async fn wait_for_log_line<R>(reader: R, pattern: &Regex, limit: Duration) -> Result<LogLine, LogWaitError>
+where
+    R: AsyncRead + Unpin
+{
+    let first = Some(BufReader::new(reader).lines());
+    let second: Option<Lines<BufReader<tokio::io::Empty>>> = None;
+
+    scan(first, second, pattern, limit).await
+}
+
+/// Read lines from both stdout and stderr of a spawned child process, until one
+/// matches a pattern.
+///
+/// The child must have been spawned with piped stdout, stderr, or both. The pipes
+/// remain with the child; output buffered past the matching line is discarded.
+///
+/// # Example
+///
+/// ```rust
+/// use std::process::Stdio;
+/// use std::time::Duration;
+/// use regex::Regex;
+/// use tokio::process::Command;
+/// use passivized_test_support::waiter::wait_for_child_log_line;
+///
+/// async fn example() -> Result<(), Box<dyn std::error::Error>> {
+///     let mut child = Command::new("my-server")
+///         .stdout(Stdio::piped())
+///         .stderr(Stdio::piped())
+///         .spawn()?;
+///
+///     let pattern = Regex::new("listening on").unwrap();
+///
+///     wait_for_child_log_line(&mut child, &pattern, Duration::from_secs(30)).await?;
+///     Ok(())
+/// }
+/// ```
+pub async fn wait_for_child_log_line(child: &mut Child, pattern: &Regex, limit: Duration) -> Result<LogLine, LogWaitError> {
+    let stdout = child.stdout
+        .as_mut()
+        .map(|stdout| BufReader::new(stdout).lines());
+
+    let stderr = child.stderr
+        .as_mut()
+        .map(|stderr| BufReader::new(stderr).lines());
+
+    if stdout.is_none() && stderr.is_none() {
+        return Err(LogWaitError::NotPiped);
+    }
+
+    scan(stdout, stderr, pattern, limit).await
+}
+
+async fn scan<A, B>(first: Option<Lines<A>>, second: Option<Lines<B>>, pattern: &Regex, limit: Duration) -> Result<LogLine, LogWaitError>
+where
+    A: AsyncBufReadExt + Unpin,
+    B: AsyncBufReadExt + Unpin
+{
+    info!("Will wait until output matches {}", pattern);
+
+    let started = Instant::now();
+    let mut captured: Vec<String> = Vec::new();
+
+    let found = timeout(limit, read_until_match(first, second, pattern, &mut captured)).await;
+
+    match found {
+        Ok(Ok(line)) => Ok(LogLine { line, captured }),
+        Ok(Err(None)) => Err(LogWaitError::Closed {
+            last_lines: last_lines(captured)
+        }),
+        Ok(Err(Some(error))) => Err(LogWaitError::Io {
+            error,
+            last_lines: last_lines(captured)
+        }),
+        Err(_) => Err(LogWaitError::TimedOut {
+            elapsed: started.elapsed(),
+            last_lines: last_lines(captured)
+        })
+    }
+}
+
+/// Fails with None if all output is closed before a line matches.
+async fn read_until_match<A, B>(mut first: Option<Lines<A>>, mut second: Option<Lines<B>>, pattern: &Regex, captured: &mut Vec<String>) -> Result<String, Option<std::io::Error>>
+where
+    A: AsyncBufReadExt + Unpin,
+    B: AsyncBufReadExt + Unpin
+{
+    loop {
+        // Lines::next_line() is cancel safe, so the losing read of the select is not lost.
+        let (from_first, read) = match (first.as_mut(), second.as_mut()) {
+            (Some(a), Some(b)) => match select(Box::pin(a.next_line()), Box::pin(b.next_line())).await {
+                Either::Left((read, _)) => (true, read),
+                Either::Right((read, _)) => (false, read)
+            },
+            (Some(a), None) => (true, a.next_line().await),
+            (None, Some(b)) => (false, b.next_line().await),
+            (None, None) => return Err(None)
+        };
+
+        match read {
+            Ok(Some(line)) => {
+                captured.push(line.clone());
+
+                if pattern.is_match(&line) {
+                    return Ok(line);
+                }
+            }
+            Ok(None) => {
+                if from_first {
+                    first = None;
+                }
+                else {
+                    second = None;
+                }
+            }
+            Err(error) => return Err(Some(error))
+        }
+    }
+}
+
+fn last_lines(mut captured: Vec<String>) -> Vec<String> {
+    let skip = captured.len().saturating_sub(LAST_LINES);
+    captured.drain(..skip);
+    captured
+}
+
+#[cfg(test)]
+mod test_wait_for_log_line {
+    use std::time::Duration;
+    use regex::Regex;
+    use tokio::io::AsyncWriteExt;
+    use crate::waiter::LogWaitError;
+    use super::wait_for_log_line;
+
+    fn listening() -> Regex {
+        Regex::new("^listening on ").unwrap()
+    }
+
+    #[tokio::test]
+    async fn fails_when_closed() {
+        let output: &[u8] = b"starting\nstopping\n";
+
+        let actual = wait_for_log_line(output, &listening(), Duration::from_secs(5))
+            .await
+            .unwrap_err();
+
+        if let LogWaitError::Closed { last_lines } = actual {
+            assert_eq!(vec!["starting", "stopping"], last_lines);
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+    }
+
+    #[tokio::test]
+    async fn finds_line() {
+        let output: &[u8] = b"starting\nlistening on 127.0.0.1:80\nready\n";
+
+        let actual = wait_for_log_line(output, &listening(), Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        assert_eq!("listening on 127.0.0.1:80", actual.line);
+        assert_eq!(vec!["starting", "listening on 127.0.0.1:80"], actual.captured);
+    }
+
+    #[tokio::test]
+    async fn times_out_with_last_lines() {
+        let (mut writer, reader) = tokio::io::duplex(1024);
+
+        for i in 0..30 {
+            writer.write_all(format!("line {}\n", i).as_bytes())
+                .await
+                .unwrap();
+        }
+
+        let actual = wait_for_log_line(reader, &listening(), Duration::from_millis(200))
+            .await
+            .unwrap_err();
+
+        if let LogWaitError::TimedOut { last_lines, .. } = actual {
+            assert_eq!(20, last_lines.len());
+            assert_eq!("line 10", last_lines[0]);
+            assert_eq!("line 29", last_lines[19]);
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+
+        drop(writer);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test_wait_for_child_log_line {
+    use std::process::Stdio;
+    use std::time::Duration;
+    use regex::Regex;
+    use tokio::process::Command;
+    use super::wait_for_child_log_line;
+
+    #[tokio::test]
+    async fn finds_line_on_stderr() {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg("echo starting; sleep 0.2; echo listening on 8080 >&2; sleep 5")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap();
+
+        let actual = wait_for_child_log_line(&mut child, &Regex::new("listening on").unwrap(), Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        assert_eq!("listening on 8080", actual.line);
+        assert_eq!(vec!["starting", "listening on 8080"], actual.captured);
+
+        child.kill()
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,434 @@
+use std::fmt::{Display, Formatter};
+use backoff::backoff::Backoff;
+use log::{debug, info};
+
+use crate::http_status_tests::is_success;
+use super::errors::MetricProbeError;
+use super::{wait_for_with_options, Probe, ProbeFuture, WaitError, WaitOptions};
+
+/// Selects series of a metric by name and, optionally, label values.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MetricMatcher {
+    name: String,
+    labels: Vec<(String, String)>
+}
+
+impl MetricMatcher {
+    pub fn new<N: Into<String>>(name: N) -> Self {
+        Self {
+            name: name.into(),
+            labels: Vec::new()
+        }
+    }
+
+    /// Additionally require a label to have a value.
+    pub fn with_label<L: Into<String>, V: Into<String>>(mut self, label: L, value: V) -> Self {
+        self.labels.push((label.into(), value.into()));
+        self
+    }
+
+    fn matches(&self, sample: &Sample) -> bool {
+        sample.name == self.name
+            && self.labels.iter().all(|expected| sample.labels.contains(expected))
+    }
+}
+
+impl Display for MetricMatcher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+
+        if !self.labels.is_empty() {
+            let labels = self.labels.iter()
+                .map(|(label, value)| format!("{}={:?}", label, value))
+                .collect::<Vec<_>>()
+                .join(",");
+
+            write!(f, "{{{}}}", labels)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Condition that the observed value of a metric must satisfy.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    Equal(f64),
+    GreaterThan(f64),
+    GreaterOrEqual(f64),
+    LessThan(f64),
+    LessOrEqual(f64)
+}
+
+impl Comparison {
+    pub fn test(&self, observed: f64) -> bool {
+        match *self {
+            Self::Equal(expected) => observed == expected,
+            Self::GreaterThan(expected) => observed > expected,
+            Self::GreaterOrEqual(expected) => observed >= expected,
+            Self::LessThan(expected) => observed < expected,
+            Self::LessOrEqual(expected) => observed <= expected
+        }
+    }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Equal(expected) => write!(f, "== {}", expected),
+            Self::GreaterThan(expected) => write!(f, "> {}", expected),
+            Self::GreaterOrEqual(expected) => write!(f, ">= {}", expected),
+            Self::LessThan(expected) => write!(f, "< {}", expected),
+            Self::LessOrEqual(expected) => write!(f, "<= {}", expected)
+        }
+    }
+}
+
+/// Probe a Prometheus `/metrics` endpoint, without TLS, returning the observed
+/// value of a metric once it satisfies a comparison.
+///
+/// If several series match, such as a counter partitioned by a label that is not
+/// matched on, their values are summed.
+pub struct MetricProbe {
+    url: String,
+    matcher: MetricMatcher,
+    comparison: Comparison
+}
+
+impl MetricProbe {
+    pub fn new<U: Into<String>>(url: U, matcher: MetricMatcher, comparison: Comparison) -> Self {
+        Self {
+            url: url.into(),
+            matcher,
+            comparison
+        }
+    }
+
+    async fn observe(&self) -> Result<f64, MetricProbeError> {
+        let text = crate::http::get_text_http_with(self.url.as_str(), is_success())
+            .await
+            .map_err(MetricProbeError::Http)?;
+
+        let mut observed = None;
+
+        for line in text.lines() {
+            match parse_sample(line) {
+                Ok(Some(sample)) if self.matcher.matches(&sample) => {
+                    observed = Some(observed.unwrap_or(0.0) + sample.value);
+                }
+                Ok(_) => {}
+                Err(error) if sample_name(line) == self.matcher.name => {
+                    return Err(MetricProbeError::Parse(error));
+                }
+                Err(error) => {
+                    debug!("Skipping unrelated line: {}", error);
+                }
+            }
+        }
+
+        let observed = observed.ok_or_else(|| MetricProbeError::Missing(self.matcher.to_string()))?;
+
+        if self.comparison.test(observed) {
+            Ok(observed)
+        }
+        else {
+            Err(MetricProbeError::Unsatisfied {
+                metric: self.matcher.to_string(),
+                observed,
+                expected: self.comparison.to_string()
+            })
+        }
+    }
+}
+
+impl Probe for MetricProbe {
+    type Output = f64;
+    type Error = MetricProbeError;
+
+    fn probe(&self) -> ProbeFuture<'_, f64, MetricProbeError> {
+        Box::pin(self.observe())
+    }
+}
+
+/// A single line of the Prometheus text exposition format.
+#[derive(Debug, PartialEq)]
+struct Sample {
+    name: String,
+    labels: Vec<(String, String)>,
+    value: f64
+}
+
+/// The metric name a sample line starts with, even if the rest does not parse.
+fn sample_name(line: &str) -> &str {
+    let line = line.trim();
+
+    line.split(|c: char| c == '{' || c.is_whitespace())
+        .next()
+        .unwrap_or(line)
+}
+
+/// Parse a sample line, or None for a blank line or comment. Timestamps are ignored.
+fn parse_sample(line: &str) -> Result<Option<Sample>, String> {
+    let line = line.trim();
+
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let invalid = || format!("invalid sample: {}", line);
+
+    let name_end = line.find(|c: char| c == '{' || c.is_whitespace())
+        .ok_or_else(invalid)?;
+
+    let (name, mut rest) = line.split_at(name_end);
+    let mut labels = Vec::new();
+
+    if let Some(after_brace) = rest.strip_prefix('{') {
+        rest = after_brace.trim_start();
+
+        while let Some(label_end) = rest.find('=').filter(|_| !rest.starts_with('}')) {
+            let label = rest[..label_end].trim();
+
+            let mut chars = rest[label_end + 1..].trim_start().char_indices();
+
+            if chars.next().map(|(_, c)| c) != Some('"') {
+                return Err(invalid());
+            }
+
+            let quoted = chars.as_str();
+            let mut value = String::new();
+            let mut closed = None;
+            let mut escaped = false;
+
+            for (index, c) in quoted.char_indices() {
+                match (escaped, c) {
+                    (true, 'n') => { value.push('\n'); escaped = false; }
+                    (true, c) => { value.push(c); escaped = false; }
+                    (false, '\\') => escaped = true,
+                    (false, '"') => { closed = Some(index); break; }
+                    (false, c) => value.push(c)
+                }
+            }
+
+            let closed = closed.ok_or_else(invalid)?;
+
+            labels.push((label.to_string(), value));
+
+            rest = quoted[closed + 1..].trim_start();
+            rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+        }
+
+        rest = rest.strip_prefix('}').ok_or_else(invalid)?;
+    }
+
+    let value = rest.split_whitespace()
+        .next()
+        .ok_or_else(invalid)?
+        .parse::<f64>()
+        .map_err(|_| invalid())?;
+
+    Ok(Some(Sample {
+        name: name.to_string(),
+        labels,
+        value
+    }))
+}
+
+/// Wait until a metric on a Prometheus `/metrics` endpoint satisfies a comparison,
+/// returning the observed value.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::{wait_for_metric, Comparison, MetricMatcher, MetricProbeError, WaitError};
+///
+/// async fn example() -> Result<(), WaitError<MetricProbeError>> {
+///     let processed = MetricMatcher::new("jobs_processed_total")
+///         .with_label("queue", "default");
+///
+///     let backoff = Limit::new(20, Constant::new(Duration::from_millis(500)));
+///
+///     wait_for_metric("http://localhost:9090/metrics", processed, Comparison::GreaterOrEqual(10.0), backoff).await?;
+///     Ok(())
+/// }
+/// ```
+pub async fn wait_for_metric<B: Backoff>(url: &str, matcher: MetricMatcher, comparison: Comparison, backoff: B) -> Result<f64, WaitError<MetricProbeError>> {
+    wait_for_metric_with_options(url, matcher, comparison, WaitOptions::new(backoff)).await
+}
+
+pub async fn wait_for_metric_with_options<B: Backoff>(url: &str, matcher: MetricMatcher, comparison: Comparison, options: WaitOptions<B>) -> Result<f64, WaitError<MetricProbeError>> {
+    info!("Will wait until {} at {} is {}", matcher, url, comparison);
+
+    wait_for_with_options(MetricProbe::new(url, matcher, comparison), options).await
+}
+
+#[cfg(test)]
+mod test_metric {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use backoff::backoff::Constant;
+    use crate::retry::Limit;
+    use crate::waiter::{MetricProbeError, Probe, WaitError};
+    use super::{parse_sample, wait_for_metric, Comparison, MetricMatcher, MetricProbe, Sample};
+
+    const PAGE: &str = r#"
+# HELP jobs_processed_total Jobs processed.
+# TYPE jobs_processed_total counter
+jobs_processed_total{queue="default",worker="a"} 3
+jobs_processed_total{queue="default",worker="b"} 4 1700000000000
+jobs_processed_total{queue="slow"} 100
+up 1
+"#;
+
+    fn backoff() -> Limit {
+        Limit::new(5, Constant::new(Duration::from_millis(10)))
+    }
+
+    #[test]
+    fn parses_escaped_labels() {
+        let actual = parse_sample(r#"requests{path="/a,b}",note="say \"hi\"\\n" , } +Inf"#)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            Sample {
+                name: "requests".into(),
+                labels: vec![("path".into(), "/a,b}".into()), ("note".into(), "say \"hi\"\\n".into())],
+                value: f64::INFINITY
+            },
+            actual
+        );
+    }
+
+    #[test]
+    fn parses_comments_and_plain() {
+        assert_eq!(None, parse_sample("# TYPE up gauge").unwrap());
+        assert_eq!(None, parse_sample("").unwrap());
+        assert_eq!(Some(Sample { name: "up".into(), labels: vec![], value: 1.0 }), parse_sample("up 1").unwrap());
+        assert!(parse_sample("up{job=\"x\" 1").is_err());
+        assert!(parse_sample("up many").is_err());
+    }
+
+    #[tokio::test]
+    async fn missing_metric() {
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("GET", "/metrics")
+            .with_body(PAGE)
+            .create_async()
+            .await;
+
+        let actual = MetricProbe::new(format!("{}/metrics", server.url()), MetricMatcher::new("down"), Comparison::Equal(1.0))
+            .probe()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual, MetricProbeError::Missing(ref metric) if metric == "down"), "{:?}", actual);
+    }
+
+    #[tokio::test]
+    async fn skips_unrelated_lines() {
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("GET", "/metrics")
+            .with_body(r#"
+# UNIT request_seconds seconds
+build_info{version=1.2.3} 1
+request_seconds_count 12 # {trace_id="4bf92f3577b34da6"} 1 1700000000.123
+up 1
+up{job=unquoted} 1
+# EOF
+"#)
+            .create_async()
+            .await;
+
+        let url = format!("{}/metrics", server.url());
+
+        let actual = MetricProbe::new(url.as_str(), MetricMatcher::new("request_seconds_count"), Comparison::Equal(12.0))
+            .probe()
+            .await
+            .unwrap();
+
+        assert_eq!(12.0, actual);
+
+        let actual = MetricProbe::new(url.as_str(), MetricMatcher::new("up"), Comparison::Equal(1.0))
+            .probe()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual, MetricProbeError::Parse(_)), "{:?}", actual);
+    }
+
+    #[tokio::test]
+    async fn sums_matching_series() {
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("GET", "/metrics")
+            .with_body(PAGE)
+            .create_async()
+            .await;
+
+        let matcher = MetricMatcher::new("jobs_processed_total")
+            .with_label("queue", "default");
+
+        let actual = MetricProbe::new(format!("{}/metrics", server.url()), matcher, Comparison::GreaterOrEqual(7.0))
+            .probe()
+            .await
+            .unwrap();
+
+        assert_eq!(7.0, actual);
+    }
+
+    #[tokio::test]
+    async fn waits_for_value() {
+        let mut server = mockito::Server::new_async().await;
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+
+        server.mock("GET", "/metrics")
+            .with_body_from_request(move |_| {
+                let call = counter.fetch_add(1, Ordering::SeqCst);
+                format!("jobs_processed_total {}\n", call * 2).into_bytes()
+            })
+            .expect_at_least(1)
+            .create_async()
+            .await;
+
+        let actual = wait_for_metric(&format!("{}/metrics", server.url()), MetricMatcher::new("jobs_processed_total"), Comparison::GreaterThan(3.0), backoff())
+            .await
+            .unwrap();
+
+        assert_eq!(4.0, actual);
+        assert_eq!(3, calls.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn unsatisfied() {
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("GET", "/metrics")
+            .with_body(PAGE)
+            .create_async()
+            .await;
+
+        let matcher = MetricMatcher::new("jobs_processed_total")
+            .with_label("queue", "slow");
+
+        let actual = wait_for_metric(&format!("{}/metrics", server.url()), matcher, Comparison::LessThan(10.0), backoff())
+            .await
+            .unwrap_err();
+
+        if let WaitError::Failed { error: MetricProbeError::Unsatisfied { metric, observed, expected }, .. } = actual {
+            assert_eq!("jobs_processed_total{queue=\"slow\"}", metric);
+            assert_eq!(100.0, observed);
+            assert_eq!("< 10", expected);
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+    }
+}
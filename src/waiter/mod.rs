@@ -0,0 +1,1378 @@
+use std::borrow::Borrow;
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::pin;
+use std::time::{Duration, Instant};
+use backoff::backoff::{Backoff, Constant};
+use futures_util::future::{select, Either};
+use log::{debug, info};
+use tokio::net::TcpStream;
+use tokio::time::{sleep, timeout};
+
+use crate::http_body_tests::BodyTest;
+use crate::http_errors::HttpError;
+use crate::http_status_tests::{is_any, StatusTest};
+use crate::retry::Limit;
+use crate::tls::TlsConnector;
+use options::Cancellation;
+
+mod addresses;
+mod all;
+mod dns;
+mod errors;
+mod file;
+mod grpc;
+mod log_line;
+mod metric;
+mod observer;
+mod options;
+mod poll;
+mod probe;
+mod report;
+mod spec;
+mod tls;
+#[cfg(unix)]
+mod unix;
+mod wire;
+
+pub use addresses::{
+    connect_tcp_server_addresses,
+    wait_for_tcp_server_addresses,
+    wait_for_tcp_server_addresses_with_options,
+    AddressPolicy,
+    AddressReport,
+    TcpAddressesProbe
+};
+pub use all::{wait_for_all, Service, ServiceReport, ServiceStatus, WaitAllReport};
+pub use dns::{wait_for_dns, wait_for_dns_with_options, DnsProbe};
+pub use errors::{
+    AddressProbeError,
+    AttemptOutcome,
+    DnsProbeError,
+    ExecProbeError,
+    FileProbeError,
+    GrpcProbeError,
+    LogWaitError,
+    MetricProbeError,
+    SpecProbeError,
+    StillUp,
+    TlsProbeError,
+    UnresolvedHost,
+    WaitError,
+    WireProbeError
+};
+pub use file::{
+    wait_for_file_matching,
+    wait_for_file_matching_with_options,
+    wait_for_file_non_empty,
+    wait_for_file_non_empty_with_options,
+    wait_for_path,
+    wait_for_path_with_options,
+    FileCondition,
+    FileProbe,
+    PathProbe
+};
+#[cfg(feature = "json")]
+pub use file::{wait_for_json_file, wait_for_json_file_with_options};
+pub use grpc::{wait_for_grpc_health, wait_for_grpc_health_with_options, GrpcHealthProbe, ServingStatus};
+pub use log_line::{wait_for_child_log_line, wait_for_log_line, LogLine};
+pub use metric::{wait_for_metric, wait_for_metric_with_options, Comparison, MetricMatcher, MetricProbe};
+pub use observer::{CollectingObserver, LoggingObserver, SilentObserver, WaitEvent, WaitObserver};
+pub use options::WaitOptions;
+pub use poll::{poll_until, poll_until_with_options, PollFailure, PollProbe};
+pub use probe::{BodyProbe, ConnectionRefused, DownProbe, ExecProbe, HttpProbe, HttpsProbe, Probe, ProbeFuture, TcpProbe};
+pub use report::{Attempt, WaitReport};
+pub use spec::{wait_for_spec, wait_for_spec_with_options, ProbeSpec, ProbeSpecError, ProbeTarget, SpecProbe};
+pub use tls::{wait_for_tls_server, wait_for_tls_server_with_options, TlsProbe};
+#[cfg(unix)]
+pub use unix::{
+    connect_unix_socket,
+    wait_for_unix_http_server,
+    wait_for_unix_http_server_with_options,
+    wait_for_unix_socket,
+    wait_for_unix_socket_with_options,
+    UnixHttpProbe,
+    UnixSocketProbe
+};
+pub use wire::{
+    wait_for_mysql,
+    wait_for_mysql_with_options,
+    wait_for_postgres,
+    wait_for_postgres_with_options,
+    wait_for_redis,
+    wait_for_redis_with_options,
+    wait_for_tcp_banner,
+    wait_for_tcp_banner_with_options,
+    BannerProbe,
+    MySqlProbe,
+    PostgresProbe,
+    RedisProbe
+};
+
+/// Repeatedly attempt a probe until it succeeds, or until the backoff policy
+/// gives up, in which case the error from the last attempt is returned.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::{wait_for, TcpProbe, WaitError};
+///
+/// async fn example() -> Result<(), WaitError<std::io::Error>> {
+///     let backoff = Limit::new(4, Constant::new(Duration::from_secs(2)));
+///
+///     wait_for(TcpProbe::new("localhost", 5432), backoff).await
+/// }
+/// ```
+pub async fn wait_for<P, B>(probe: P, backoff: B) -> Result<P::Output, WaitError<P::Error>>
+where
+    P: Probe,
+    B: Backoff
+{
+    wait_for_with_options(probe, WaitOptions::new(backoff)).await
+}
+
+/// Repeatedly attempt a probe until it succeeds, the backoff policy gives up,
+/// or the deadline passes.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::{wait_for_with_options, TcpProbe, WaitError, WaitOptions};
+///
+/// async fn example() -> Result<(), WaitError<std::io::Error>> {
+///     let options = WaitOptions::new(Limit::new(20, Constant::new(Duration::from_secs(1))))
+///         .with_deadline(Duration::from_secs(15))
+///         .with_attempt_timeout(Duration::from_secs(3));
+///
+///     wait_for_with_options(TcpProbe::new("localhost", 5432), options).await
+/// }
+/// ```
+pub async fn wait_for_with_options<P, B>(probe: P, options: WaitOptions<B>) -> Result<P::Output, WaitError<P::Error>>
+where
+    P: Probe,
+    B: Backoff
+{
+    wait_for_report_with_options(probe, options)
+        .await
+        .map(|report| report.value)
+}
+
+/// Same as wait_for(), but report every attempt made along with the final value.
+pub async fn wait_for_report<P, B>(probe: P, backoff: B) -> Result<WaitReport<P::Output>, WaitError<P::Error>>
+where
+    P: Probe,
+    B: Backoff
+{
+    wait_for_report_with_options(probe, WaitOptions::new(backoff)).await
+}
+
+/// Same as wait_for_with_options(), but report every attempt made along with
+/// the final value. If the wait fails, the attempts are in the error's
+/// [`attempt_log`](WaitError::attempt_log).
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use log::info;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::{wait_for_report_with_options, TcpProbe, WaitError, WaitOptions};
+///
+/// async fn example() -> Result<(), WaitError<std::io::Error>> {
+///     let options = WaitOptions::new(Limit::new(20, Constant::new(Duration::from_secs(1))));
+///
+///     let report = wait_for_report_with_options(TcpProbe::new("localhost", 5432), options).await?;
+///
+///     info!("Database {}", report);
+///
+///     for attempt in report.failures() {
+///         info!("Attempt took {:?}: {:?}", attempt.duration, attempt.error);
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub async fn wait_for_report_with_options<P, B>(probe: P, mut options: WaitOptions<B>) -> Result<WaitReport<P::Output>, WaitError<P::Error>>
+where
+    P: Probe,
+    B: Backoff
+{
+    options.backoff.reset();
+
+    let started = Instant::now();
+    let mut attempts: usize = 0;
+    let mut consecutive: usize = 0;
+    let mut records: Vec<Attempt> = Vec::new();
+    let mut last_error: Option<P::Error> = None;
+
+    loop {
+        attempts += 1;
+
+        options.observer.on_attempt(attempts);
+
+        let attempt_started = started.elapsed();
+        let attempt_limit = options.attempt_limit(attempt_started);
+
+        let attempt = async {
+            match attempt_limit {
+                None => Some(probe.probe().await),
+                Some(limit) => timeout(limit, probe.probe()).await.ok()
+            }
+        };
+
+        let outcome = unless_cancelled(&mut options.cancellation, attempt).await;
+
+        let mut record = Attempt {
+            outcome: AttemptOutcome::Succeeded,
+            started: attempt_started,
+            duration: started.elapsed() - attempt_started,
+            error: None
+        };
+
+        let error = match outcome {
+            None => {
+                record.outcome = AttemptOutcome::Cancelled;
+                records.push(record);
+
+                let error = cancelled(attempts, started, records, last_error);
+                options.observer.on_give_up(&error);
+                return Err(error);
+            }
+            Some(Some(Ok(output))) => {
+                records.push(record);
+                consecutive += 1;
+
+                let elapsed = started.elapsed();
+
+                if consecutive >= options.stable_successes {
+                    options.observer.on_success(attempts, elapsed);
+
+                    return Ok(WaitReport {
+                        attempts: records,
+                        elapsed,
+                        value: output
+                    });
+                }
+
+                if options.exceeds_deadline(elapsed, options.stable_interval) {
+                    let error = WaitError::Unstable { attempts, elapsed, history: outcomes(&records), last_error, attempt_log: records };
+                    options.observer.on_give_up(&error);
+                    return Err(error);
+                }
+
+                debug!("Attempt {} succeeded, {} of {} needed in a row", attempts, consecutive, options.stable_successes);
+
+                if unless_cancelled(&mut options.cancellation, sleep(options.stable_interval)).await.is_none() {
+                    let error = cancelled(attempts, started, records, last_error);
+                    options.observer.on_give_up(&error);
+                    return Err(error);
+                }
+
+                continue;
+            }
+            Some(Some(Err(error))) => {
+                record.outcome = AttemptOutcome::Failed;
+                record.error = Some(format!("{:?}", error));
+                records.push(record);
+
+                if probe.is_permanent(&error) {
+                    let error = WaitError::Permanent { attempts, elapsed: started.elapsed(), error, attempt_log: records };
+                    options.observer.on_give_up(&error);
+                    return Err(error);
+                }
+
+                Some(error)
+            }
+            Some(None) => {
+                record.outcome = AttemptOutcome::TimedOut;
+                records.push(record);
+                None
+            }
+        };
+
+        consecutive = 0;
+
+        let elapsed = started.elapsed();
+
+        let give_up_with = match (error, options.backoff.next_backoff()) {
+            (Some(error), None) => {
+                give_up(attempts, elapsed, records, Some(error), last_error, false)
+            }
+            (None, None) => {
+                give_up(attempts, elapsed, records, None, last_error, true)
+            }
+            (error, Some(delay)) if options.exceeds_deadline(elapsed, delay) => {
+                give_up(attempts, elapsed, records, error, last_error, true)
+            }
+            (error, Some(delay)) => {
+                options.observer.on_retry(attempts, delay, error.as_ref().map(|error| error as &dyn Debug));
+
+                if error.is_some() {
+                    last_error = error;
+                }
+
+                if unless_cancelled(&mut options.cancellation, sleep(delay)).await.is_some() {
+                    continue;
+                }
+
+                cancelled(attempts, started, records, last_error)
+            }
+        };
+
+        options.observer.on_give_up(&give_up_with);
+        return Err(give_up_with);
+    }
+}
+
+/// Run a future to completion, unless cancellation is signalled first, in which
+/// case the result is None.
+async fn unless_cancelled<F: Future>(cancellation: &mut Option<Cancellation>, work: F) -> Option<F::Output> {
+    match cancellation {
+        None => Some(work.await),
+        Some(cancellation) => {
+            match select(cancellation, pin!(work)).await {
+                Either::Left(_) => None,
+                Either::Right((output, _)) => Some(output)
+            }
+        }
+    }
+}
+
+fn cancelled<E>(attempts: usize, started: Instant, records: Vec<Attempt>, last_error: Option<E>) -> WaitError<E> {
+    WaitError::Cancelled {
+        attempts,
+        elapsed: started.elapsed(),
+        history: outcomes(&records),
+        last_error,
+        attempt_log: records
+    }
+}
+
+fn outcomes(records: &[Attempt]) -> Vec<AttemptOutcome> {
+    records
+        .iter()
+        .map(|record| record.outcome)
+        .collect()
+}
+
+/// Error for when there will be no more attempts, given the error from the final
+/// attempt (if it did not time out) and the most recent error before that.
+fn give_up<E>(attempts: usize, elapsed: Duration, records: Vec<Attempt>, error: Option<E>, last_error: Option<E>, timed_out: bool) -> WaitError<E> {
+    let history = outcomes(&records);
+
+    if history.contains(&AttemptOutcome::Succeeded) {
+        return WaitError::Unstable { attempts, elapsed, history, last_error: error.or(last_error), attempt_log: records };
+    }
+
+    match error {
+        Some(error) if !timed_out => WaitError::Failed { attempts, elapsed, error, attempt_log: records },
+        error => WaitError::TimedOut { attempts, elapsed, last_error: error.or(last_error), attempt_log: records }
+    }
+}
+
+/// Wait for the server to be available and the URL to return an HTTP status
+/// code that meets an expectation.
+///
+/// Gives up after at most 30 seconds; see [`WaitOptions::default`].
+///
+/// # Example
+///
+/// ```rust
+/// use std::error::Error;
+/// use passivized_test_support::http_status_tests::is_success;
+/// use passivized_test_support::waiter::wait_for_http_server;
+///
+/// async fn example() -> Result<(), Box<dyn Error>> {
+///     wait_for_http_server("http://foo/missing", is_success()).await;
+///     Ok(())
+/// }
+/// ```
+pub async fn wait_for_http_server<U, T>(url: U, status_test: T) -> Result<String, HttpError>
+where
+    U: Borrow<str>,
+    T: StatusTest + Copy,
+{
+    wait_for_http_server_with_options(url, status_test, WaitOptions::default())
+        .await
+        .map_err(HttpError::from)
+}
+
+pub async fn wait_for_http_server_with_backoff<U, T, B>(url: U, status_test: T, backoff: B) -> Result<String, HttpError>
+where
+    U: Borrow<str>,
+    T: StatusTest + Copy,
+    B: Backoff
+{
+    wait_for_http_server_with_options(url, status_test, WaitOptions::new(backoff))
+        .await
+        .map_err(HttpError::from)
+}
+
+pub async fn wait_for_http_server_with_options<U, T, B>(url: U, status_test: T, options: WaitOptions<B>) -> Result<String, WaitError<HttpError>>
+where
+    U: Borrow<str>,
+    T: StatusTest,
+    B: Backoff
+{
+    wait_for_http_server_report_with_options(url, status_test, options)
+        .await
+        .map(|report| report.value)
+}
+
+/// Same as wait_for_http_server_with_options(), but report every attempt made
+/// along with the response body.
+pub async fn wait_for_http_server_report_with_options<U, T, B>(url: U, status_test: T, options: WaitOptions<B>) -> Result<WaitReport<String>, WaitError<HttpError>>
+where
+    U: Borrow<str>,
+    T: StatusTest,
+    B: Backoff
+{
+    let borrowed = url.borrow();
+
+    info!("Will wait until can connect to {}", borrowed);
+
+    wait_for_report_with_options(HttpProbe::new(borrowed, status_test), options).await
+}
+
+/// Wait for the server to be available and the URL to return an HTTP status
+/// code that meets an expectation.
+///
+/// Gives up after at most 30 seconds; see [`WaitOptions::default`].
+///
+/// # Example
+///
+/// ```rust
+/// use std::error::Error;
+/// use passivized_test_support::tls::TlsConnector;
+/// use passivized_test_support::http_status_tests::is_success;
+/// use passivized_test_support::waiter::wait_for_https_server;
+///
+/// async fn example() -> Result<(), Box<dyn Error>> {
+///     wait_for_https_server("http://foo/missing", TlsConnector::new()?, is_success()).await;
+///     Ok(())
+/// }
+/// ```
+pub async fn wait_for_https_server<U, T>(url: U, tls: TlsConnector, status_test: T) -> Result<String, HttpError>
+where
+    U: Borrow<str>,
+    T: StatusTest + Copy
+{
+    let options = WaitOptions {
+        backoff: Limit::new(5, Constant::new(Duration::from_secs(2))),
+        ..WaitOptions::default()
+    };
+
+    wait_for_https_server_with_options(url, tls, status_test, options)
+        .await
+        .map_err(HttpError::from)
+}
+
+pub async fn wait_for_https_server_with_backoff<U, T, B>(url: U, tls: TlsConnector, status_test: T, backoff: B) -> Result<String, HttpError>
+where
+    U: Borrow<str>,
+    T: StatusTest + Copy,
+    B: Backoff
+{
+    wait_for_https_server_with_options(url, tls, status_test, WaitOptions::new(backoff))
+        .await
+        .map_err(HttpError::from)
+}
+
+pub async fn wait_for_https_server_with_options<U, T, B>(url: U, tls: TlsConnector, status_test: T, options: WaitOptions<B>) -> Result<String, WaitError<HttpError>>
+where
+    U: Borrow<str>,
+    T: StatusTest,
+    B: Backoff
+{
+    wait_for_https_server_report_with_options(url, tls, status_test, options)
+        .await
+        .map(|report| report.value)
+}
+
+/// Same as wait_for_https_server_with_options(), but report every attempt made
+/// along with the response body.
+pub async fn wait_for_https_server_report_with_options<U, T, B>(url: U, tls: TlsConnector, status_test: T, options: WaitOptions<B>) -> Result<WaitReport<String>, WaitError<HttpError>>
+where
+    U: Borrow<str>,
+    T: StatusTest,
+    B: Backoff
+{
+    let borrowed = url.borrow();
+
+    info!("Will wait until can connect to {}", borrowed);
+
+    wait_for_report_with_options(HttpsProbe::new(borrowed, tls, status_test), options).await
+}
+
+/// Wait for the server to be available, the URL to return an HTTP status
+/// code that meets an expectation, and the response body to pass a test.
+///
+/// # Example
+///
+/// ```rust
+/// use std::error::Error;
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use passivized_test_support::http_body_tests::contains;
+/// use passivized_test_support::http_status_tests::is_success;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::wait_for_http_body;
+///
+/// async fn example() -> Result<(), Box<dyn Error>> {
+///     let backoff = Limit::new(7, Constant::new(Duration::from_secs(2)));
+///
+///     wait_for_http_body("http://foo/health", is_success(), contains("\"status\":\"ok\""), backoff).await?;
+///     Ok(())
+/// }
+/// ```
+pub async fn wait_for_http_body<U, T, BT, B>(url: U, status_test: T, body_test: BT, backoff: B) -> Result<String, HttpError>
+where
+    U: Borrow<str>,
+    T: StatusTest,
+    BT: BodyTest,
+    B: Backoff
+{
+    wait_for_http_body_with_options(url, status_test, body_test, WaitOptions::new(backoff))
+        .await
+        .map_err(HttpError::from)
+}
+
+pub async fn wait_for_http_body_with_options<U, T, BT, B>(url: U, status_test: T, body_test: BT, options: WaitOptions<B>) -> Result<String, WaitError<HttpError>>
+where
+    U: Borrow<str>,
+    T: StatusTest,
+    BT: BodyTest,
+    B: Backoff
+{
+    let borrowed = url.borrow();
+
+    info!("Will wait until {} returns the expected body", borrowed);
+
+    wait_for_with_options(BodyProbe::new(HttpProbe::new(borrowed, status_test), body_test), options).await
+}
+
+/// Same as wait_for_http_body(), but for a server that is using TLS/HTTPS.
+pub async fn wait_for_https_body<U, T, BT, B>(url: U, tls: TlsConnector, status_test: T, body_test: BT, backoff: B) -> Result<String, HttpError>
+where
+    U: Borrow<str>,
+    T: StatusTest,
+    BT: BodyTest,
+    B: Backoff
+{
+    wait_for_https_body_with_options(url, tls, status_test, body_test, WaitOptions::new(backoff))
+        .await
+        .map_err(HttpError::from)
+}
+
+pub async fn wait_for_https_body_with_options<U, T, BT, B>(url: U, tls: TlsConnector, status_test: T, body_test: BT, options: WaitOptions<B>) -> Result<String, WaitError<HttpError>>
+where
+    U: Borrow<str>,
+    T: StatusTest,
+    BT: BodyTest,
+    B: Backoff
+{
+    let borrowed = url.borrow();
+
+    info!("Will wait until {} returns the expected body", borrowed);
+
+    wait_for_with_options(BodyProbe::new(HttpsProbe::new(borrowed, tls, status_test), body_test), options).await
+}
+
+pub async fn connect_tcp_server(host: &str, port: u16) -> Result<(), std::io::Error> {
+    info!("Will wait until can connect to {}:{}", host, port);
+
+    let addresses = dns::resolve(host, port).await?;
+
+    let connection = TcpStream::connect(addresses.as_slice()).await?;
+    drop(connection);
+    Ok(())
+}
+
+/// Wait until a connection to a TCP server succeeds, giving up after at most
+/// 30 seconds.
+pub async fn wait_for_tcp_server(host: &str, port: u16) -> Result<(), std::io::Error> {
+    let options = WaitOptions {
+        backoff: Limit::new(4, Constant::new(Duration::from_secs(2))),
+        ..WaitOptions::default()
+    };
+
+    wait_for_tcp_server_with_options(host, port, options)
+        .await
+        .map_err(std::io::Error::from)
+}
+
+pub async fn wait_for_tcp_server_with_backoff<B: Backoff>(host: &str, port: u16, backoff: B) -> Result<(), std::io::Error> {
+    wait_for_tcp_server_with_options(host, port, WaitOptions::new(backoff))
+        .await
+        .map_err(std::io::Error::from)
+}
+
+pub async fn wait_for_tcp_server_with_options<B: Backoff>(host: &str, port: u16, options: WaitOptions<B>) -> Result<(), WaitError<std::io::Error>> {
+    wait_for_tcp_server_report_with_options(host, port, options)
+        .await
+        .map(|report| report.value)
+}
+
+/// Same as wait_for_tcp_server_with_options(), but report every attempt made.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use log::warn;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::{wait_for_tcp_server_report_with_options, WaitOptions};
+///
+/// async fn example() {
+///     let options = WaitOptions::new(Limit::new(20, Constant::new(Duration::from_secs(1))));
+///
+///     if let Err(error) = wait_for_tcp_server_report_with_options("localhost", 5432, options).await {
+///         for attempt in error.attempt_log() {
+///             warn!("Attempt took {:?}: {:?}", attempt.duration, attempt.error);
+///         }
+///     }
+/// }
+/// ```
+pub async fn wait_for_tcp_server_report_with_options<B: Backoff>(host: &str, port: u16, options: WaitOptions<B>) -> Result<WaitReport<()>, WaitError<std::io::Error>> {
+    wait_for_report_with_options(TcpProbe::new(host, port), options).await
+}
+
+/// Wait until connections to a server are refused, such as after shutting it
+/// down, and before another server rebinds the port.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::{wait_for_tcp_server_down, StillUp, WaitError};
+///
+/// async fn example() -> Result<(), WaitError<StillUp>> {
+///     let backoff = Limit::new(10, Constant::new(Duration::from_millis(500)));
+///
+///     wait_for_tcp_server_down("localhost", 8080, backoff).await
+/// }
+/// ```
+pub async fn wait_for_tcp_server_down<B: Backoff>(host: &str, port: u16, backoff: B) -> Result<(), WaitError<StillUp>> {
+    wait_for_tcp_server_down_with_options(host, port, WaitOptions::new(backoff)).await
+}
+
+pub async fn wait_for_tcp_server_down_with_options<B: Backoff>(host: &str, port: u16, options: WaitOptions<B>) -> Result<(), WaitError<StillUp>> {
+    info!("Will wait until can no longer connect to {}:{}", host, port);
+
+    wait_for_with_options(DownProbe::new(TcpProbe::new(host, port), format!("{}:{}", host, port)), options).await
+}
+
+/// Wait until a server no longer returns any HTTP response for a URL.
+pub async fn wait_for_http_server_down<U, B>(url: U, backoff: B) -> Result<(), WaitError<StillUp>>
+where
+    U: Borrow<str>,
+    B: Backoff
+{
+    wait_for_http_server_down_with_options(url, WaitOptions::new(backoff)).await
+}
+
+pub async fn wait_for_http_server_down_with_options<U, B>(url: U, options: WaitOptions<B>) -> Result<(), WaitError<StillUp>>
+where
+    U: Borrow<str>,
+    B: Backoff
+{
+    let borrowed = url.borrow();
+
+    info!("Will wait until can no longer connect to {}", borrowed);
+
+    wait_for_with_options(DownProbe::new(HttpProbe::new(borrowed, is_any()), borrowed), options).await
+}
+
+#[cfg(test)]
+mod test_wait_for {
+    use std::cell::Cell;
+    use std::future::pending;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use backoff::backoff::Constant;
+    use tokio::sync::oneshot;
+    use crate::retry::Limit;
+    use super::{
+        wait_for,
+        wait_for_report,
+        wait_for_report_with_options,
+        wait_for_with_options,
+        AttemptOutcome,
+        CollectingObserver,
+        Probe,
+        ProbeFuture,
+        WaitError,
+        WaitEvent,
+        WaitOptions
+    };
+
+    /// Fails until it has been attempted a given number of times.
+    struct Countdown {
+        attempts: Cell<usize>,
+        failures: usize
+    }
+
+    impl Countdown {
+        fn new(failures: usize) -> Self {
+            Self {
+                attempts: Cell::new(0),
+                failures
+            }
+        }
+    }
+
+    impl Probe for Countdown {
+        type Output = usize;
+        type Error = String;
+
+        fn probe(&self) -> ProbeFuture<'_, usize, String> {
+            let attempt = self.attempts.get() + 1;
+            self.attempts.set(attempt);
+
+            Box::pin(async move {
+                if attempt > self.failures {
+                    Ok(attempt)
+                }
+                else {
+                    Err(format!("attempt {}", attempt))
+                }
+            })
+        }
+    }
+
+    fn backoff(retries: usize) -> Limit {
+        Limit::new(retries, Constant::new(Duration::from_millis(10)))
+    }
+
+    /// Hangs on the first attempt, and succeeds on later attempts.
+    struct HangsOnce {
+        attempts: Cell<usize>
+    }
+
+    impl Probe for HangsOnce {
+        type Output = usize;
+        type Error = String;
+
+        fn probe(&self) -> ProbeFuture<'_, usize, String> {
+            let attempt = self.attempts.get() + 1;
+            self.attempts.set(attempt);
+
+            Box::pin(async move {
+                if attempt == 1 {
+                    pending::<()>().await;
+                }
+
+                Ok(attempt)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn aborts_hung_attempt() {
+        let probe = HangsOnce {
+            attempts: Cell::new(0)
+        };
+
+        let options = WaitOptions::new(backoff(3))
+            .with_attempt_timeout(Duration::from_millis(100));
+
+        let actual = wait_for_with_options(probe, options)
+            .await
+            .unwrap();
+
+        assert_eq!(2, actual);
+    }
+
+    #[tokio::test]
+    async fn cancels_during_attempt() {
+        let (cancel, cancelled) = oneshot::channel::<()>();
+
+        let probe = HangsOnce {
+            attempts: Cell::new(0)
+        };
+
+        let options = WaitOptions::new(backoff(3))
+            .with_cancellation(async move {
+                let _ = cancelled.await;
+            });
+
+        let waiting = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            cancel.send(()).unwrap();
+        });
+
+        let actual = wait_for_with_options(probe, options)
+            .await
+            .unwrap_err();
+
+        waiting.await.unwrap();
+
+        if let WaitError::Cancelled { attempts, history, last_error, .. } = actual {
+            assert_eq!(1, attempts);
+            assert_eq!(vec![AttemptOutcome::Cancelled], history);
+            assert_eq!(None, last_error);
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+    }
+
+    #[tokio::test]
+    async fn cancels_between_attempts() {
+        let options = WaitOptions::new(Limit::new(100, Constant::new(Duration::from_millis(50))))
+            .with_cancellation(tokio::time::sleep(Duration::from_millis(175)));
+
+        let actual = wait_for_with_options(Countdown::new(1000), options)
+            .await
+            .unwrap_err();
+
+        if let WaitError::Cancelled { attempts, history, last_error, .. } = actual {
+            assert!(attempts > 1);
+            assert_eq!(attempts, history.len());
+            assert!(history.iter().all(|outcome| *outcome == AttemptOutcome::Failed));
+            assert_eq!(Some(format!("attempt {}", attempts)), last_error);
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+    }
+
+    #[tokio::test]
+    async fn gives_up_with_last_error() {
+        let actual = wait_for(Countdown::new(5), backoff(2))
+            .await
+            .unwrap_err();
+
+        if let WaitError::Failed { attempts, error, .. } = actual {
+            assert_eq!(3, attempts);
+            assert_eq!("attempt 3", error);
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+    }
+
+    /// Succeeds only on listed attempts.
+    struct Flapping {
+        attempts: Cell<usize>,
+        successes: Vec<usize>
+    }
+
+    impl Flapping {
+        fn new(successes: Vec<usize>) -> Self {
+            Self {
+                attempts: Cell::new(0),
+                successes
+            }
+        }
+    }
+
+    impl Probe for Flapping {
+        type Output = usize;
+        type Error = String;
+
+        fn probe(&self) -> ProbeFuture<'_, usize, String> {
+            let attempt = self.attempts.get() + 1;
+            self.attempts.set(attempt);
+
+            Box::pin(async move {
+                if self.successes.contains(&attempt) {
+                    Ok(attempt)
+                }
+                else {
+                    Err(format!("attempt {}", attempt))
+                }
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_history_when_unstable() {
+        let options = WaitOptions::new(backoff(3))
+            .with_stability(2, Duration::from_millis(10));
+
+        let actual = wait_for_with_options(Flapping::new(vec![1, 3, 5]), options)
+            .await
+            .unwrap_err();
+
+        if let WaitError::Unstable { attempts, history, last_error, .. } = actual {
+            use AttemptOutcome::{Failed, Succeeded};
+
+            assert_eq!(7, attempts);
+            assert_eq!(vec![Succeeded, Failed, Succeeded, Failed, Succeeded, Failed, Failed], history);
+            assert_eq!(Some("attempt 7".to_string()), last_error);
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+    }
+
+    #[tokio::test]
+    async fn notifies_observer() {
+        let observer = Arc::new(CollectingObserver::default());
+
+        let options = WaitOptions::new(backoff(3))
+            .with_observer(observer.clone());
+
+        wait_for_with_options(Countdown::new(1), options)
+            .await
+            .unwrap();
+
+        let events = observer.events();
+
+        assert_eq!(4, events.len());
+        assert_eq!(WaitEvent::Attempt(1), events[0]);
+        assert_eq!(
+            WaitEvent::Retry {
+                attempt: 1,
+                delay: Duration::from_millis(10),
+                error: Some("\"attempt 1\"".into())
+            },
+            events[1]
+        );
+        assert_eq!(WaitEvent::Attempt(2), events[2]);
+        assert!(matches!(events[3], WaitEvent::Success { attempts: 2, .. }));
+    }
+
+    #[tokio::test]
+    async fn notifies_observer_of_give_up() {
+        let observer = Arc::new(CollectingObserver::default());
+
+        let options = WaitOptions::new(backoff(1))
+            .with_observer(observer.clone());
+
+        wait_for_with_options(Countdown::new(5), options)
+            .await
+            .unwrap_err();
+
+        assert_eq!(1, observer.retries());
+
+        match observer.events().last() {
+            Some(WaitEvent::GiveUp(error)) => assert!(error.starts_with("gave up after 2 attempts")),
+            other => panic!("Unexpected event: {:?}", other)
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_attempts() {
+        let report = wait_for_report(Countdown::new(2), backoff(3))
+            .await
+            .unwrap();
+
+        assert_eq!(3, report.value);
+        assert_eq!(3, report.attempts.len());
+        assert_eq!(2, report.failures().count());
+
+        assert_eq!(AttemptOutcome::Failed, report.attempts[0].outcome);
+        assert_eq!(Some("\"attempt 1\"".to_string()), report.attempts[0].error);
+        assert_eq!(AttemptOutcome::Succeeded, report.attempts[2].outcome);
+        assert_eq!(None, report.attempts[2].error);
+
+        // Each attempt starts after the delay from the previous one
+        assert!(report.attempts[1].started >= report.attempts[0].started + Duration::from_millis(10));
+        assert!(report.elapsed >= report.attempts[2].started);
+
+        assert!(report.to_string().starts_with("ready after 3 attempts (2 failed) in "));
+    }
+
+    #[tokio::test]
+    async fn reports_attempts_when_failed() {
+        let actual = wait_for_report(Countdown::new(5), backoff(2))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual, WaitError::Failed { .. }), "Unexpected error: {:?}", actual);
+        assert_eq!(3, actual.attempt_log().len());
+        assert_eq!(Some("\"attempt 3\"".to_string()), actual.attempt_log()[2].error);
+        assert!(actual.attempt_log().iter().all(|attempt| attempt.outcome == AttemptOutcome::Failed));
+    }
+
+    #[tokio::test]
+    async fn reports_timed_out_attempt() {
+        let probe = HangsOnce {
+            attempts: Cell::new(0)
+        };
+
+        let options = WaitOptions::new(backoff(3))
+            .with_attempt_timeout(Duration::from_millis(100));
+
+        let report = wait_for_report_with_options(probe, options)
+            .await
+            .unwrap();
+
+        assert_eq!(2, report.value);
+        assert_eq!(AttemptOutcome::TimedOut, report.attempts[0].outcome);
+        assert!(report.attempts[0].duration >= Duration::from_millis(100));
+        assert_eq!(None, report.attempts[0].error);
+    }
+
+    #[tokio::test]
+    async fn requires_consecutive_successes() {
+        let options = WaitOptions::new(backoff(5))
+            .with_stability(3, Duration::from_millis(10));
+
+        let actual = wait_for_with_options(Flapping::new(vec![1, 3, 4, 5, 6]), options)
+            .await
+            .unwrap();
+
+        assert_eq!(5, actual);
+    }
+
+    #[tokio::test]
+    async fn times_out_at_deadline() {
+        let options = WaitOptions::new(backoff(1000))
+            .with_deadline(Duration::from_millis(200));
+
+        let started = Instant::now();
+
+        let actual = wait_for_with_options(Countdown::new(1000), options)
+            .await
+            .unwrap_err();
+
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        if let WaitError::TimedOut { attempts, last_error, .. } = actual {
+            assert!(attempts > 1);
+            assert_eq!(Some(format!("attempt {}", attempts)), last_error);
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+    }
+
+    #[tokio::test]
+    async fn times_out_when_attempt_hangs_past_deadline() {
+        let options = WaitOptions::new(backoff(3))
+            .with_deadline(Duration::from_millis(100));
+
+        let actual = wait_for_with_options(HangsOnce { attempts: Cell::new(0) }, options)
+            .await
+            .unwrap_err();
+
+        if let WaitError::TimedOut { attempts, last_error, .. } = actual {
+            assert_eq!(1, attempts);
+            assert_eq!(None, last_error);
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_until_success() {
+        let probe = Countdown::new(2);
+
+        let actual = wait_for(&probe, backoff(3))
+            .await
+            .unwrap();
+
+        assert_eq!(3, actual);
+    }
+}
+
+#[cfg(test)]
+mod test_wait_for_http_server {
+    use std::time::Duration;
+    use backoff::backoff::Constant;
+    use http::StatusCode;
+    use crate::http_status_tests::{equals, is_success};
+    use crate::retry::Limit;
+    use crate::waiter::{wait_for_http_server, wait_for_http_server_report_with_options, AttemptOutcome, WaitOptions};
+
+    #[tokio::test]
+    async fn reports_attempts_when_failed() {
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("GET", "/starting")
+            .with_status(503)
+            .create_async()
+            .await;
+
+        let options = WaitOptions::new(Limit::new(1, Constant::new(Duration::from_millis(10))));
+
+        let actual = wait_for_http_server_report_with_options(format!("{}/starting", server.url()), is_success(), options)
+            .await
+            .unwrap_err();
+
+        assert_eq!(2, actual.attempt_log().len());
+        assert_eq!(AttemptOutcome::Failed, actual.attempt_log()[1].outcome);
+        assert!(actual.attempt_log()[1].error.as_ref().unwrap().contains("503"));
+    }
+
+    #[tokio::test]
+    async fn waits_for_expected_failure() {
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("GET", "/abc")
+            .with_status(404)
+            .with_body("xyz")
+            .create_async()
+            .await;
+
+        let actual = wait_for_http_server(format!("{}/abc", server.url()), equals(StatusCode::NOT_FOUND))
+            .await
+            .unwrap();
+
+        assert_eq!("xyz", actual);
+    }
+
+    #[tokio::test]
+    async fn waits_for_success() {
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("GET", "/fizz")
+            .with_body("buzz")
+            .create_async()
+            .await;
+
+        let actual = wait_for_http_server(format!("{}/fizz", server.url()), is_success())
+            .await
+            .unwrap();
+
+        assert_eq!("buzz", actual);
+    }
+}
+
+#[cfg(test)]
+mod test_wait_for_http_body {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use backoff::backoff::Constant;
+    use regex::Regex;
+    use crate::http_body_tests::{contains, matches};
+    use crate::http_errors::HttpError;
+    use crate::http_status_tests::is_success;
+    use crate::retry::Limit;
+    use crate::waiter::wait_for_http_body;
+
+    fn backoff(retries: usize) -> Limit {
+        Limit::new(retries, Constant::new(Duration::from_millis(10)))
+    }
+
+    #[tokio::test]
+    async fn fails_with_last_body() {
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("GET", "/health")
+            .with_body(r#"{"status":"starting"}"#)
+            .create_async()
+            .await;
+
+        let actual = wait_for_http_body(format!("{}/health", server.url()), is_success(), contains("ready"), backoff(2))
+            .await
+            .unwrap_err();
+
+        if let HttpError::UnexpectedBody(body) = actual {
+            assert_eq!(r#"{"status":"starting"}"#, body);
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+    }
+
+    #[tokio::test]
+    async fn waits_for_expected_body() {
+        let mut server = mockito::Server::new_async().await;
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+
+        server.mock("GET", "/health")
+            .with_body_from_request(move |_| {
+                if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                    r#"{"status":"starting"}"#.into()
+                }
+                else {
+                    r#"{"status":"ready"}"#.into()
+                }
+            })
+            .create_async()
+            .await;
+
+        let body_test = matches(Regex::new(r#""status":"ready""#).unwrap());
+
+        let actual = wait_for_http_body(format!("{}/health", server.url()), is_success(), body_test, backoff(5))
+            .await
+            .unwrap();
+
+        assert_eq!(r#"{"status":"ready"}"#, actual);
+        assert_eq!(3, requests.load(Ordering::SeqCst));
+    }
+}
+
+#[cfg(test)]
+mod test_wait_for_server_down {
+    use std::convert::Infallible;
+    use std::time::Duration;
+    use backoff::backoff::Constant;
+    use http::{Request, Response};
+    use http_body_util::Full;
+    use hyper::body::{Bytes, Incoming};
+    use hyper::service::service_fn;
+    use hyper_util::rt::TokioIo;
+    use tokio::net::TcpListener;
+    use crate::retry::Limit;
+    use crate::waiter::{wait_for_http_server_down, wait_for_tcp_server_down, WaitError};
+
+    fn backoff() -> Limit {
+        Limit::new(20, Constant::new(Duration::from_millis(50)))
+    }
+
+    #[tokio::test]
+    async fn fails_while_http_server_up() {
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("GET", "/")
+            .with_status(503)
+            .create_async()
+            .await;
+
+        let actual = wait_for_http_server_down(server.url(), Limit::new(1, Constant::new(Duration::from_millis(10))))
+            .await
+            .unwrap_err();
+
+        if let WaitError::Failed { attempts, error, .. } = actual {
+            assert_eq!(2, attempts);
+            assert_eq!(format!("{} is still accepting connections", server.url()), error.to_string());
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_while_http_server_closes_connections() {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap();
+
+        let port = listener.local_addr()
+            .unwrap()
+            .port();
+
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept()
+                    .await
+                    .unwrap();
+
+                drop(stream);
+            }
+        });
+
+        let actual = wait_for_http_server_down(format!("http://127.0.0.1:{}/", port), Limit::new(1, Constant::new(Duration::from_millis(10))))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual, WaitError::Failed { .. }), "Unexpected error: {:?}", actual);
+    }
+
+    #[tokio::test]
+    async fn fails_while_tcp_server_up() {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap();
+
+        let port = listener.local_addr()
+            .unwrap()
+            .port();
+
+        let actual = wait_for_tcp_server_down("127.0.0.1", port, Limit::new(1, Constant::new(Duration::from_millis(10))))
+            .await
+            .unwrap_err();
+
+        if let WaitError::Failed { error, .. } = actual {
+            assert_eq!(format!("127.0.0.1:{}", port), error.target);
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+    }
+
+    #[tokio::test]
+    async fn waits_for_http_server_down() {
+        let port = {
+            let listener = TcpListener::bind("127.0.0.1:0")
+                .await
+                .unwrap();
+
+            listener.local_addr()
+                .unwrap()
+                .port()
+        };
+
+        wait_for_http_server_down(format!("http://127.0.0.1:{}/", port), backoff())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn waits_for_http_server_down_after_pooled_request() {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap();
+
+        let url = format!("http://127.0.0.1:{}/", listener.local_addr().unwrap().port());
+
+        // Connections already accepted stay open after the server stops accepting.
+        let server = tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept()
+                    .await
+                    .unwrap();
+
+                let service = service_fn(|_: Request<Incoming>| async {
+                    Ok::<_, Infallible>(Response::new(Full::new(Bytes::new())))
+                });
+
+                tokio::spawn(async move {
+                    let _ = hyper::server::conn::http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await;
+                });
+            }
+        });
+
+        crate::http::get_text_http(url.as_str())
+            .await
+            .unwrap();
+
+        server.abort();
+        let _ = server.await;
+
+        wait_for_http_server_down(url.as_str(), backoff())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn waits_for_tcp_server_down() {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap();
+
+        let port = listener.local_addr()
+            .unwrap()
+            .port();
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            drop(listener);
+        });
+
+        wait_for_tcp_server_down("127.0.0.1", port, backoff())
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,160 @@
+use std::fmt::{Debug, Display};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use log::warn;
+
+/// Notified of progress while waiting, such as to log, count attempts, or
+/// record events for assertions.
+///
+/// All methods do nothing by default, so an implementation only needs to
+/// override the events it is interested in.
+pub trait WaitObserver {
+
+    /// An attempt, numbered from 1, is about to start.
+    fn on_attempt(&self, _attempt: usize) {}
+
+    /// An attempt failed, and the next attempt will start after a delay.
+    ///
+    /// The error is None if the attempt timed out.
+    fn on_retry(&self, _attempt: usize, _delay: Duration, _error: Option<&dyn Debug>) {}
+
+    /// The wait succeeded.
+    fn on_success(&self, _attempts: usize, _elapsed: Duration) {}
+
+    /// There will be no more attempts.
+    fn on_give_up(&self, _error: &dyn Display) {}
+
+}
+
+impl<O: WaitObserver + ?Sized> WaitObserver for Arc<O> {
+    fn on_attempt(&self, attempt: usize) {
+        (**self).on_attempt(attempt)
+    }
+
+    fn on_retry(&self, attempt: usize, delay: Duration, error: Option<&dyn Debug>) {
+        (**self).on_retry(attempt, delay, error)
+    }
+
+    fn on_success(&self, attempts: usize, elapsed: Duration) {
+        (**self).on_success(attempts, elapsed)
+    }
+
+    fn on_give_up(&self, error: &dyn Display) {
+        (**self).on_give_up(error)
+    }
+}
+
+/// Logs a warning before each retry. This is the default observer.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LoggingObserver {}
+
+impl WaitObserver for LoggingObserver {
+    fn on_retry(&self, attempt: usize, _delay: Duration, error: Option<&dyn Debug>) {
+        match error {
+            Some(error) => warn!("Retrying after failure: {:?}", error),
+            None => warn!("Retrying after attempt {} timed out", attempt)
+        }
+    }
+}
+
+/// Ignores all events, for suites where retries are expected and noisy.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SilentObserver {}
+
+impl WaitObserver for SilentObserver {}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum WaitEvent {
+    Attempt(usize),
+
+    Retry {
+        attempt: usize,
+        delay: Duration,
+
+        /// Debug representation of the error, or None if the attempt timed out.
+        error: Option<String>
+    },
+
+    Success {
+        attempts: usize,
+        elapsed: Duration
+    },
+
+    GiveUp(String)
+}
+
+/// Records every event, for assertions after waiting.
+///
+/// # Example
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::{wait_for_with_options, CollectingObserver, TcpProbe, WaitOptions};
+///
+/// async fn example() {
+///     let observer = Arc::new(CollectingObserver::default());
+///
+///     let options = WaitOptions::new(Limit::new(4, Constant::new(Duration::from_secs(2))))
+///         .with_observer(observer.clone());
+///
+///     let _ = wait_for_with_options(TcpProbe::new("localhost", 5432), options).await;
+///
+///     println!("Retried {} times", observer.retries());
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct CollectingObserver {
+    events: Mutex<Vec<WaitEvent>>
+}
+
+impl CollectingObserver {
+
+    pub fn events(&self) -> Vec<WaitEvent> {
+        self.events
+            .lock()
+            .unwrap()
+            .clone()
+    }
+
+    pub fn retries(&self) -> usize {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|event| matches!(event, WaitEvent::Retry { .. }))
+            .count()
+    }
+
+    fn push(&self, event: WaitEvent) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(event);
+    }
+
+}
+
+impl WaitObserver for CollectingObserver {
+    fn on_attempt(&self, attempt: usize) {
+        self.push(WaitEvent::Attempt(attempt));
+    }
+
+    fn on_retry(&self, attempt: usize, delay: Duration, error: Option<&dyn Debug>) {
+        self.push(WaitEvent::Retry {
+            attempt,
+            delay,
+            error: error.map(|error| format!("{:?}", error))
+        });
+    }
+
+    fn on_success(&self, attempts: usize, elapsed: Duration) {
+        self.push(WaitEvent::Success { attempts, elapsed });
+    }
+
+    fn on_give_up(&self, error: &dyn Display) {
+        self.push(WaitEvent::GiveUp(error.to_string()));
+    }
+}
@@ -0,0 +1,190 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+use backoff::backoff::{Backoff, Constant};
+
+use crate::retry::Limit;
+use super::observer::{LoggingObserver, WaitObserver};
+
+/// How long, and how often, to keep attempting a probe.
+///
+/// The backoff policy decides the delay between attempts and when to give up.
+/// Independently of the policy, an overall deadline bounds the total wall-clock
+/// time of the wait, and a per-attempt timeout aborts a single attempt that hangs,
+/// such as a connect to a half-open socket.
+///
+/// For services that flap during startup, a number of consecutive successful
+/// attempts can be required before the service is considered ready.
+///
+/// Progress is reported to an observer, which by default logs each retry.
+///
+/// The wait can be cancelled from outside, such as when a container being waited
+/// on has already exited, by any future that completes to signal cancellation.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::WaitOptions;
+///
+/// let options = WaitOptions::new(Limit::new(7, Constant::new(Duration::from_secs(2))))
+///     .with_deadline(Duration::from_secs(30))
+///     .with_attempt_timeout(Duration::from_secs(5))
+///     .with_stability(3, Duration::from_secs(1));
+/// ```
+pub struct WaitOptions<B> {
+    pub(super) backoff: B,
+    pub(super) deadline: Option<Duration>,
+    pub(super) attempt_timeout: Option<Duration>,
+    pub(super) stable_successes: usize,
+    pub(super) stable_interval: Duration,
+    pub(super) observer: Box<dyn WaitObserver>,
+    pub(super) cancellation: Option<Cancellation>
+}
+
+pub(super) type Cancellation = Pin<Box<dyn Future<Output = ()>>>;
+
+impl<B: Backoff> WaitOptions<B> {
+
+    pub fn new(backoff: B) -> Self {
+        Self {
+            backoff,
+            deadline: None,
+            attempt_timeout: None,
+            stable_successes: 1,
+            stable_interval: Duration::ZERO,
+            observer: Box::new(LoggingObserver {}),
+            cancellation: None
+        }
+    }
+
+    /// Give up once this much time has passed since the wait started, even if
+    /// the backoff policy would continue.
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Abort any single attempt that takes longer than this.
+    pub fn with_attempt_timeout(mut self, attempt_timeout: Duration) -> Self {
+        self.attempt_timeout = Some(attempt_timeout);
+        self
+    }
+
+    /// Only consider the probe successful after this many successful attempts in
+    /// a row, each spaced by the interval. A failed attempt starts the count over.
+    pub fn with_stability(mut self, successes: usize, interval: Duration) -> Self {
+        self.stable_successes = successes;
+        self.stable_interval = interval;
+        self
+    }
+
+    /// Report progress to an observer instead of logging each retry.
+    pub fn with_observer<O: WaitObserver + 'static>(mut self, observer: O) -> Self {
+        self.observer = Box::new(observer);
+        self
+    }
+
+    /// Stop waiting as soon as a future completes, even during an attempt.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use backoff::backoff::Constant;
+    /// use tokio::sync::oneshot;
+    /// use passivized_test_support::retry::Limit;
+    /// use passivized_test_support::waiter::WaitOptions;
+    ///
+    /// let (cancel, cancelled) = oneshot::channel::<()>();
+    ///
+    /// let options = WaitOptions::new(Limit::new(7, Constant::new(Duration::from_secs(2))))
+    ///     .with_cancellation(async move {
+    ///         let _ = cancelled.await;
+    ///     });
+    ///
+    /// // Elsewhere, such as a task watching a container:
+    /// cancel.send(()).unwrap();
+    /// ```
+    pub fn with_cancellation<F: Future<Output = ()> + 'static>(mut self, cancellation: F) -> Self {
+        self.cancellation = Some(Box::pin(cancellation));
+        self
+    }
+
+    /// Time limit for the next attempt, given how long the wait has been running.
+    pub(super) fn attempt_limit(&self, elapsed: Duration) -> Option<Duration> {
+        let remaining = self.deadline
+            .map(|deadline| deadline.saturating_sub(elapsed));
+
+        match (self.attempt_timeout, remaining) {
+            (Some(attempt_timeout), Some(remaining)) => Some(attempt_timeout.min(remaining)),
+            (attempt_timeout, remaining) => attempt_timeout.or(remaining)
+        }
+    }
+
+    /// Whether the deadline would pass before an attempt could start after a delay.
+    pub(super) fn exceeds_deadline(&self, elapsed: Duration, delay: Duration) -> bool {
+        match self.deadline {
+            Some(deadline) => elapsed + delay >= deadline,
+            None => false
+        }
+    }
+
+}
+
+/// The options used by waiters that don't take any: up to 8 attempts, 2 seconds
+/// apart, each limited to 10 seconds, and no more than 30 seconds in total.
+impl Default for WaitOptions<Limit> {
+    fn default() -> Self {
+        Self::new(Limit::new(7, Constant::new(Duration::from_secs(2))))
+            .with_deadline(Duration::from_secs(30))
+            .with_attempt_timeout(Duration::from_secs(10))
+    }
+}
+
+#[cfg(test)]
+mod test_attempt_limit {
+    use std::time::Duration;
+    use backoff::backoff::Zero;
+    use super::WaitOptions;
+
+    fn secs(n: u64) -> Duration {
+        Duration::from_secs(n)
+    }
+
+    #[test]
+    fn default_is_limited() {
+        let options = WaitOptions::default();
+
+        assert_eq!(Some(secs(10)), options.attempt_limit(secs(0)));
+        assert_eq!(Some(secs(2)), options.attempt_limit(secs(28)));
+    }
+
+    #[test]
+    fn unlimited() {
+        let options = WaitOptions::new(Zero {});
+
+        assert_eq!(None, options.attempt_limit(secs(100)));
+    }
+
+    #[test]
+    fn limited_by_attempt_timeout() {
+        let options = WaitOptions::new(Zero {})
+            .with_attempt_timeout(secs(5))
+            .with_deadline(secs(30));
+
+        assert_eq!(Some(secs(5)), options.attempt_limit(secs(10)));
+    }
+
+    #[test]
+    fn limited_by_remaining_time() {
+        let options = WaitOptions::new(Zero {})
+            .with_attempt_timeout(secs(5))
+            .with_deadline(secs(30));
+
+        assert_eq!(Some(secs(2)), options.attempt_limit(secs(28)));
+        assert_eq!(Some(Duration::ZERO), options.attempt_limit(secs(31)));
+    }
+}
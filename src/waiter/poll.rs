@@ -0,0 +1,232 @@
+use std::fmt::Debug;
+use std::future::Future;
+use std::marker::PhantomData;
+use backoff::backoff::Backoff;
+
+use super::{wait_for_with_options, Probe, ProbeFuture, WaitError, WaitOptions};
+
+/// Why a single poll did not succeed.
+#[derive(Debug, thiserror::Error)]
+pub enum PollFailure<T, E> {
+    /// The value was obtained, but did not satisfy the predicate.
+    #[error("not yet satisfied: {0:?}")]
+    Unsatisfied(T),
+
+    #[error("{0:?}")]
+    Transient(E),
+
+    /// Polling stops without any further attempts.
+    #[error("{0:?}")]
+    Permanent(E)
+}
+
+impl<T, E> PollFailure<T, E> {
+
+    /// The most recent value, if one was obtained.
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            Self::Unsatisfied(value) => Some(value),
+            _ => None
+        }
+    }
+
+}
+
+/// Probe that calls a closure, and succeeds when the value it returns satisfies
+/// a predicate.
+///
+/// Errors returned by the closure are transient, and retried, unless they are
+/// [`backoff::Error::Permanent`].
+pub struct PollProbe<F, Fut, C> {
+    poll: F,
+    predicate: C,
+
+    // Only so that the `Fut` type parameter, otherwise unused by the fields, is allowed.
+    future: PhantomData<fn() -> Fut>
+}
+
+impl<F, Fut, T, E, C> PollProbe<F, Fut, C>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, backoff::Error<E>>>,
+    C: Fn(&T) -> bool
+{
+    pub fn new(poll: F, predicate: C) -> Self {
+        Self {
+            poll,
+            predicate,
+            future: PhantomData
+        }
+    }
+}
+
+impl<F, Fut, T, E, C> Probe for PollProbe<F, Fut, C>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, backoff::Error<E>>>,
+    T: Debug,
+    E: Debug,
+    C: Fn(&T) -> bool
+{
+    type Output = T;
+    type Error = PollFailure<T, E>;
+
+    fn probe(&self) -> ProbeFuture<'_, T, PollFailure<T, E>> {
+        let polling = (self.poll)();
+
+        Box::pin(async move {
+            match polling.await {
+                Ok(value) if (self.predicate)(&value) => Ok(value),
+                Ok(value) => Err(PollFailure::Unsatisfied(value)),
+                Err(backoff::Error::Transient { err, .. }) => Err(PollFailure::Transient(err)),
+                Err(backoff::Error::Permanent(err)) => Err(PollFailure::Permanent(err))
+            }
+        })
+    }
+
+    fn is_permanent(&self, error: &PollFailure<T, E>) -> bool {
+        matches!(error, PollFailure::Permanent(_))
+    }
+}
+
+/// Repeatedly call a closure until the value it returns satisfies a predicate,
+/// returning that value.
+///
+/// The closure returns [`backoff::Error::transient`] for errors worth retrying,
+/// and [`backoff::Error::permanent`] for errors that stop polling immediately.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use passivized_test_support::http::get_text_http;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::poll_until;
+///
+/// async fn example() {
+///     let backoff = Limit::new(30, Constant::new(Duration::from_secs(1)));
+///
+///     let status = poll_until(
+///         || async {
+///             get_text_http("http://localhost:8080/jobs/1/status")
+///                 .await
+///                 .map_err(|e| backoff::Error::transient(e.to_string()))
+///         },
+///         |status| status == "done" || status == "failed",
+///         backoff
+///     )
+///         .await
+///         .unwrap();
+/// }
+/// ```
+pub async fn poll_until<F, Fut, T, E, C, B>(poll: F, predicate: C, backoff: B) -> Result<T, WaitError<PollFailure<T, E>>>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, backoff::Error<E>>>,
+    T: Debug,
+    E: Debug,
+    C: Fn(&T) -> bool,
+    B: Backoff
+{
+    poll_until_with_options(poll, predicate, WaitOptions::new(backoff)).await
+}
+
+pub async fn poll_until_with_options<F, Fut, T, E, C, B>(poll: F, predicate: C, options: WaitOptions<B>) -> Result<T, WaitError<PollFailure<T, E>>>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, backoff::Error<E>>>,
+    T: Debug,
+    E: Debug,
+    C: Fn(&T) -> bool,
+    B: Backoff
+{
+    wait_for_with_options(PollProbe::new(poll, predicate), options).await
+}
+
+#[cfg(test)]
+mod test_poll_until {
+    use std::cell::Cell;
+    use std::time::Duration;
+    use backoff::backoff::Constant;
+    use crate::retry::Limit;
+    use crate::waiter::WaitError;
+    use super::{poll_until, PollFailure};
+
+    fn backoff(retries: usize) -> Limit {
+        Limit::new(retries, Constant::new(Duration::from_millis(10)))
+    }
+
+    #[tokio::test]
+    async fn fails_with_last_value() {
+        let actual = poll_until(
+            || async { Ok::<_, backoff::Error<String>>("running") },
+            |status| *status == "done",
+            backoff(2)
+        )
+            .await
+            .unwrap_err();
+
+        if let WaitError::Failed { attempts, error, .. } = actual {
+            assert_eq!(3, attempts);
+            assert_eq!(Some(&"running"), error.value());
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_transient_errors() {
+        let calls = Cell::new(0);
+
+        let actual = poll_until(
+            || {
+                let call = calls.get() + 1;
+                calls.set(call);
+
+                async move {
+                    match call {
+                        1 => Err(backoff::Error::transient("unavailable")),
+                        2 => Ok("running"),
+                        _ => Ok("done")
+                    }
+                }
+            },
+            |status| *status == "done",
+            backoff(5)
+        )
+            .await
+            .unwrap();
+
+        assert_eq!("done", actual);
+        assert_eq!(3, calls.get());
+    }
+
+    #[tokio::test]
+    async fn stops_on_permanent_error() {
+        let calls = Cell::new(0);
+
+        let actual = poll_until(
+            || {
+                calls.set(calls.get() + 1);
+
+                async { Err::<&str, _>(backoff::Error::permanent("no such job")) }
+            },
+            |_| true,
+            backoff(5)
+        )
+            .await
+            .unwrap_err();
+
+        assert_eq!(1, calls.get());
+
+        if let WaitError::Permanent { attempts, error, .. } = actual {
+            assert_eq!(1, attempts);
+            assert!(matches!(error, PollFailure::Permanent("no such job")));
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+    }
+}
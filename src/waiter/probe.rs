@@ -0,0 +1,365 @@
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use log::debug;
+
+use crate::http_body_tests::BodyTest;
+use crate::http_client::HttpClient;
+use crate::http_errors::HttpError;
+use crate::http_status_tests::StatusTest;
+use crate::tls::TlsConnector;
+use super::errors::{ExecProbeError, StillUp};
+
+/// Future returned by a single probe attempt.
+pub type ProbeFuture<'a, T, E> = Pin<Box<dyn Future<Output = Result<T, E>> + 'a>>;
+
+/// A single readiness check, attempted repeatedly by [`wait_for`](super::wait_for)
+/// until it succeeds or the backoff policy gives up.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::{wait_for, Probe, ProbeFuture, WaitError};
+///
+/// struct AlwaysReady;
+///
+/// impl Probe for AlwaysReady {
+///     type Output = ();
+///     type Error = String;
+///
+///     fn probe(&self) -> ProbeFuture<'_, (), String> {
+///         Box::pin(async { Ok(()) })
+///     }
+/// }
+///
+/// async fn example() -> Result<(), WaitError<String>> {
+///     wait_for(AlwaysReady, Limit::new(3, Constant::new(Duration::from_secs(1)))).await
+/// }
+/// ```
+pub trait Probe {
+    type Output;
+    type Error: Debug;
+
+    fn probe(&self) -> ProbeFuture<'_, Self::Output, Self::Error>;
+
+    /// Whether an error means there is no point in any further attempts.
+    fn is_permanent(&self, _error: &Self::Error) -> bool {
+        false
+    }
+}
+
+impl<P: Probe + ?Sized> Probe for &P {
+    type Output = P::Output;
+    type Error = P::Error;
+
+    fn probe(&self) -> ProbeFuture<'_, Self::Output, Self::Error> {
+        (**self).probe()
+    }
+
+    fn is_permanent(&self, error: &Self::Error) -> bool {
+        (**self).is_permanent(error)
+    }
+}
+
+/// Probe a URL on a server that is not using TLS/HTTPS, returning the response body.
+///
+/// Each attempt opens a new connection, so that a server that stops accepting
+/// connections is noticed, rather than hidden by a connection kept open.
+pub struct HttpProbe<T> {
+    url: String,
+    client: HttpClient,
+    status_test: T
+}
+
+impl<T: StatusTest> HttpProbe<T> {
+    pub fn new<U: Into<String>>(url: U, status_test: T) -> Self {
+        Self {
+            url: url.into(),
+            client: HttpClient::unpooled(),
+            status_test
+        }
+    }
+}
+
+impl<T: StatusTest> Probe for HttpProbe<T> {
+    type Output = String;
+    type Error = HttpError;
+
+    fn probe(&self) -> ProbeFuture<'_, String, HttpError> {
+        Box::pin(self.client.get_text_with(self.url.as_str(), &self.status_test))
+    }
+}
+
+/// Probe a URL on a server that is using TLS/HTTPS, returning the response body.
+///
+/// Connections are reused between attempts.
+pub struct HttpsProbe<T> {
+    url: String,
+    client: HttpClient,
+    status_test: T
+}
+
+impl<T: StatusTest> HttpsProbe<T> {
+    pub fn new<U: Into<String>>(url: U, tls: TlsConnector, status_test: T) -> Self {
+        Self {
+            url: url.into(),
+            client: HttpClient::with_tls(tls),
+            status_test
+        }
+    }
+}
+
+impl<T: StatusTest> Probe for HttpsProbe<T> {
+    type Output = String;
+    type Error = HttpError;
+
+    fn probe(&self) -> ProbeFuture<'_, String, HttpError> {
+        Box::pin(self.client.get_text_with(self.url.as_str(), &self.status_test))
+    }
+}
+
+/// Wraps an HTTP or HTTPS probe, additionally requiring the response body to pass
+/// a test, for services that respond successfully before they are actually ready.
+///
+/// A body that fails the test is reported as [`HttpError::UnexpectedBody`].
+pub struct BodyProbe<P, BT> {
+    inner: P,
+    body_test: BT
+}
+
+impl<P, BT> BodyProbe<P, BT>
+where
+    P: Probe<Output = String, Error = HttpError>,
+    BT: BodyTest
+{
+    pub fn new(inner: P, body_test: BT) -> Self {
+        Self {
+            inner,
+            body_test
+        }
+    }
+}
+
+impl<P, BT> Probe for BodyProbe<P, BT>
+where
+    P: Probe<Output = String, Error = HttpError>,
+    BT: BodyTest
+{
+    type Output = String;
+    type Error = HttpError;
+
+    fn probe(&self) -> ProbeFuture<'_, String, HttpError> {
+        Box::pin(async move {
+            let body = self.inner.probe().await?;
+
+            if self.body_test.test(&body) {
+                Ok(body)
+            }
+            else {
+                Err(HttpError::UnexpectedBody(body))
+            }
+        })
+    }
+}
+
+/// Probe a server by opening, and then immediately closing, a TCP connection.
+pub struct TcpProbe {
+    host: String,
+    port: u16
+}
+
+impl TcpProbe {
+    pub fn new<H: Into<String>>(host: H, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port
+        }
+    }
+}
+
+impl Probe for TcpProbe {
+    type Output = ();
+    type Error = std::io::Error;
+
+    fn probe(&self) -> ProbeFuture<'_, (), std::io::Error> {
+        Box::pin(super::connect_tcp_server(&self.host, self.port))
+    }
+}
+
+/// Probe by running a command, such as `pg_isready`, and expecting it to exit
+/// successfully.
+///
+/// The command is killed if the attempt times out. A command that cannot be
+/// found fails permanently.
+pub struct ExecProbe {
+    program: String,
+    args: Vec<String>
+}
+
+impl ExecProbe {
+    pub fn new<P, A, S>(program: P, args: A) -> Self
+    where
+        P: Into<String>,
+        A: IntoIterator<Item = S>,
+        S: Into<String>
+    {
+        Self {
+            program: program.into(),
+            args: args.into_iter().map(Into::into).collect()
+        }
+    }
+
+    async fn run(&self) -> Result<(), ExecProbeError> {
+        let output = tokio::process::Command::new(&self.program)
+            .args(&self.args)
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(ExecProbeError::Spawn)?;
+
+        if output.status.success() {
+            Ok(())
+        }
+        else {
+            Err(ExecProbeError::Failed {
+                status: output.status,
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_string()
+            })
+        }
+    }
+}
+
+impl Probe for ExecProbe {
+    type Output = ();
+    type Error = ExecProbeError;
+
+    fn probe(&self) -> ProbeFuture<'_, (), ExecProbeError> {
+        Box::pin(self.run())
+    }
+
+    fn is_permanent(&self, error: &ExecProbeError) -> bool {
+        matches!(error, ExecProbeError::Spawn(e) if e.kind() == std::io::ErrorKind::NotFound)
+    }
+}
+
+/// Errors that show a server has shut down: the connection was refused or reset.
+pub trait ConnectionRefused {
+    fn is_connection_refused(&self) -> bool;
+}
+
+impl ConnectionRefused for std::io::Error {
+    fn is_connection_refused(&self) -> bool {
+        matches!(self.kind(), std::io::ErrorKind::ConnectionRefused | std::io::ErrorKind::ConnectionReset)
+    }
+}
+
+impl ConnectionRefused for HttpError {
+    fn is_connection_refused(&self) -> bool {
+        let mut source: Option<&(dyn std::error::Error + 'static)> = match self {
+            HttpError::Other(error) => Some(error.as_ref()),
+            _ => None
+        };
+
+        while let Some(error) = source {
+            if let Some(io) = error.downcast_ref::<std::io::Error>() {
+                return io.is_connection_refused();
+            }
+
+            source = error.source();
+        }
+
+        false
+    }
+}
+
+/// Inverts another probe: succeeds when the inner probe fails because the
+/// connection was refused, such as when a server has shut down.
+///
+/// Any other failure, such as an error status or a failed TLS handshake, means
+/// the server is still up.
+pub struct DownProbe<P> {
+    inner: P,
+    target: String
+}
+
+impl<P: Probe> DownProbe<P> {
+    /// The target describes what is expected to be down, for error messages.
+    pub fn new<S: Into<String>>(inner: P, target: S) -> Self {
+        Self {
+            inner,
+            target: target.into()
+        }
+    }
+}
+
+impl<P> Probe for DownProbe<P>
+where
+    P: Probe,
+    P::Error: ConnectionRefused
+{
+    type Output = ();
+    type Error = StillUp;
+
+    fn probe(&self) -> ProbeFuture<'_, (), StillUp> {
+        Box::pin(async move {
+            match self.inner.probe().await {
+                Err(error) if error.is_connection_refused() => {
+                    debug!("{} is down: {:?}", self.target, error);
+                    Ok(())
+                },
+                Err(error) => {
+                    debug!("{} is still up: {:?}", self.target, error);
+                    Err(StillUp {
+                        target: self.target.clone()
+                    })
+                },
+                Ok(_) => Err(StillUp {
+                    target: self.target.clone()
+                })
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test_tcp_probe {
+    use tokio::net::TcpListener;
+    use super::{Probe, TcpProbe};
+
+    #[tokio::test]
+    async fn connects() {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap();
+
+        let port = listener.local_addr()
+            .unwrap()
+            .port();
+
+        TcpProbe::new("127.0.0.1", port)
+            .probe()
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn fails_when_nothing_listening() {
+        let port = {
+            let listener = TcpListener::bind("127.0.0.1:0")
+                .await
+                .unwrap();
+
+            listener.local_addr()
+                .unwrap()
+                .port()
+        };
+
+        TcpProbe::new("127.0.0.1", port)
+            .probe()
+            .await
+            .unwrap_err();
+    }
+}
@@ -0,0 +1,49 @@
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+use super::errors::AttemptOutcome;
+
+/// A single attempt of a probe.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Attempt {
+    pub outcome: AttemptOutcome,
+
+    /// When the attempt started, relative to when the wait started.
+    pub started: Duration,
+
+    pub duration: Duration,
+
+    /// Debug representation of the error, if the attempt failed without timing out.
+    pub error: Option<String>
+}
+
+/// How a successful wait went, and the value from the final attempt.
+#[derive(Clone, Debug)]
+pub struct WaitReport<T> {
+    pub attempts: Vec<Attempt>,
+    pub elapsed: Duration,
+    pub value: T
+}
+
+impl<T> WaitReport<T> {
+
+    /// The attempts that did not succeed.
+    pub fn failures(&self) -> impl Iterator<Item = &Attempt> {
+        self.attempts
+            .iter()
+            .filter(|attempt| attempt.outcome != AttemptOutcome::Succeeded)
+    }
+
+}
+
+impl<T> Display for WaitReport<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ready after {} attempts ({} failed) in {:.1} seconds",
+            self.attempts.len(),
+            self.failures().count(),
+            self.elapsed.as_secs_f64()
+        )
+    }
+}
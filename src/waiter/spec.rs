@@ -0,0 +1,641 @@
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use backoff::backoff::Backoff;
+use http::StatusCode;
+use log::info;
+
+use crate::http_client::HttpClient;
+use crate::http_status_tests::StatusTest;
+use crate::tls::TlsConnector;
+use super::errors::SpecProbeError;
+use super::{wait_for_with_options, ExecProbe, Probe, ProbeFuture, TcpProbe, WaitError, WaitOptions};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProbeSpecError {
+    #[error("invalid address in probe spec: {0}")]
+    InvalidAddress(String),
+
+    #[error("invalid value for probe spec option {option}: {value}")]
+    InvalidValue {
+        option: String,
+        value: String
+    },
+
+    #[error("missing command in probe spec")]
+    MissingCommand,
+
+    #[error("unsupported probe spec option for this scheme: {0}")]
+    UnsupportedOption(String),
+
+    #[error("unsupported probe spec scheme: {0}")]
+    UnsupportedScheme(String)
+}
+
+/// What a [`ProbeSpec`] checks.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ProbeTarget {
+    /// A TCP connection can be opened.
+    Tcp {
+        host: String,
+        port: u16
+    },
+
+    /// A GET of the URL succeeds, or returns a specific status.
+    Http {
+        url: String,
+        status: Option<StatusCode>
+    },
+
+    /// A GET of the URL succeeds, or returns a specific status, optionally
+    /// accepting any certificate.
+    Https {
+        url: String,
+        status: Option<StatusCode>,
+        insecure: bool
+    },
+
+    /// A connection to a Unix domain socket can be opened or, with a path, a GET
+    /// of that path over the socket succeeds.
+    Unix {
+        socket: PathBuf,
+        path: Option<String>,
+        status: Option<StatusCode>
+    },
+
+    /// A command exits successfully.
+    Exec {
+        program: String,
+        args: Vec<String>
+    }
+}
+
+/// A probe described by a string, such as from an environment variable or a test
+/// configuration file.
+///
+/// Supported forms:
+///
+/// * `tcp://host:port`
+/// * `http://host:port/path` and `https://host:port/path`, with options
+///   `status=404`, and for HTTPS, `insecure=true` to accept any certificate.
+///   Other query parameters are kept in the URL.
+/// * `unix:///path/to/socket`, with options `path=/_ping` to make an HTTP
+///   request over the socket, and `status`.
+/// * `exec:command arg1 arg2`, with arguments split on whitespace. Quotes are
+///   not interpreted, so an argument can't contain whitespace; wrap such commands
+///   in a script. Everything after `exec:` is the command, including any `?`, so
+///   this form takes no options.
+///
+/// The other forms accept `timeout`, such as `timeout=30s`, `timeout=500ms` or
+/// `timeout=2m`, which becomes the overall deadline of the wait. Options follow
+/// the first `?`, separated by `&`.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use passivized_test_support::waiter::{ProbeSpec, ProbeTarget};
+///
+/// let spec: ProbeSpec = "http://localhost:8080/health?status=204&timeout=30s".parse().unwrap();
+///
+/// assert_eq!(Some(Duration::from_secs(30)), spec.timeout());
+///
+/// if let ProbeTarget::Http { url, status } = spec.target() {
+///     assert_eq!("http://localhost:8080/health", url);
+///     assert_eq!(Some(204), status.map(|s| s.as_u16()));
+/// }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProbeSpec {
+    text: String,
+    target: ProbeTarget,
+    timeout: Option<Duration>
+}
+
+impl ProbeSpec {
+
+    pub fn target(&self) -> &ProbeTarget {
+        &self.target
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// A probe that checks the target. Fails if the TLS connector for an HTTPS
+    /// target can't be built.
+    pub fn probe(&self) -> Result<SpecProbe, SpecProbeError> {
+        let https = match &self.target {
+            ProbeTarget::Https { insecure, .. } => {
+                let tls = TlsConnector::builder()
+                    .danger_accept_invalid_certs(*insecure)
+                    .build()
+                    .map_err(SpecProbeError::Tls)?;
+
+                Some(HttpClient::with_tls(tls))
+            }
+            _ => None
+        };
+
+        Ok(SpecProbe {
+            target: self.target.clone(),
+            https
+        })
+    }
+}
+
+impl Display for ProbeSpec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+impl FromStr for ProbeSpec {
+    type Err = ProbeSpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(command) = s.strip_prefix("exec:") {
+            let mut words = command.split_whitespace();
+
+            let target = ProbeTarget::Exec {
+                program: words.next().ok_or(ProbeSpecError::MissingCommand)?.into(),
+                args: words.map(Into::into).collect()
+            };
+
+            return Ok(Self {
+                text: s.into(),
+                target,
+                timeout: None
+            });
+        }
+
+        let (base, query) = match s.split_once('?') {
+            Some((base, query)) => (base, query),
+            None => (s, "")
+        };
+
+        let mut options = Options::parse(query);
+        let timeout = options.take("timeout")
+            .map(|value| parse_duration(&value).ok_or(ProbeSpecError::InvalidValue { option: "timeout".into(), value }))
+            .transpose()?;
+
+        let target = if let Some(address) = base.strip_prefix("tcp://") {
+            let (host, port) = address.rsplit_once(':')
+                .ok_or_else(|| ProbeSpecError::InvalidAddress(address.into()))?;
+
+            let host = host.trim_start_matches('[').trim_end_matches(']');
+            let port = port.parse::<u16>()
+                .map_err(|_| ProbeSpecError::InvalidAddress(address.into()))?;
+
+            if host.is_empty() {
+                return Err(ProbeSpecError::InvalidAddress(address.into()));
+            }
+
+            ProbeTarget::Tcp {
+                host: host.into(),
+                port
+            }
+        }
+        else if base.starts_with("http://") {
+            ProbeTarget::Http {
+                status: options.take_status()?,
+                url: options.append_to(base)
+            }
+        }
+        else if base.starts_with("https://") {
+            ProbeTarget::Https {
+                status: options.take_status()?,
+                insecure: options.take_bool("insecure")?,
+                url: options.append_to(base)
+            }
+        }
+        else if let Some(socket) = base.strip_prefix("unix://") {
+            if socket.is_empty() {
+                return Err(ProbeSpecError::InvalidAddress(base.into()));
+            }
+
+            ProbeTarget::Unix {
+                socket: socket.into(),
+                path: options.take("path"),
+                status: options.take_status()?
+            }
+        }
+        else {
+            let scheme = base.split_once(':')
+                .map(|(scheme, _)| scheme)
+                .unwrap_or(base);
+
+            return Err(ProbeSpecError::UnsupportedScheme(scheme.into()));
+        };
+
+        options.finish()?;
+
+        Ok(Self {
+            text: s.into(),
+            target,
+            timeout
+        })
+    }
+}
+
+/// Options in the query of a spec, removed as each is recognized.
+struct Options {
+    /// Names and values, where a flag such as `?verbose` has no value.
+    pairs: Vec<(String, Option<String>)>,
+    /// Whether unrecognized options are kept, as query parameters of a URL.
+    passed_through: bool
+}
+
+impl Options {
+
+    fn parse(query: &str) -> Self {
+        let pairs = query.split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (pair.to_string(), None)
+            })
+            .collect();
+
+        Self {
+            pairs,
+            passed_through: false
+        }
+    }
+
+    fn take_entry(&mut self, name: &str) -> Option<Option<String>> {
+        let index = self.pairs.iter().position(|(n, _)| n == name)?;
+
+        Some(self.pairs.remove(index).1)
+    }
+
+    /// The value of an option, which is empty if given as a flag.
+    fn take(&mut self, name: &str) -> Option<String> {
+        self.take_entry(name)
+            .map(Option::unwrap_or_default)
+    }
+
+    /// Whether an option is true, which it is if given as a flag.
+    fn take_bool(&mut self, name: &str) -> Result<bool, ProbeSpecError> {
+        match self.take_entry(name) {
+            None => Ok(false),
+            Some(None) => Ok(true),
+            Some(Some(value)) => value.parse()
+                .map_err(|_| ProbeSpecError::InvalidValue { option: name.into(), value })
+        }
+    }
+
+    fn take_status(&mut self) -> Result<Option<StatusCode>, ProbeSpecError> {
+        self.take("status")
+            .map(|value| StatusCode::from_str(&value).map_err(|_| ProbeSpecError::InvalidValue { option: "status".into(), value }))
+            .transpose()
+    }
+
+    /// The URL with the remaining options as its query.
+    fn append_to(&mut self, url: &str) -> String {
+        self.passed_through = true;
+
+        if self.pairs.is_empty() {
+            return url.into();
+        }
+
+        let query = self.pairs.iter()
+            .map(|(name, value)| match value {
+                Some(value) => format!("{}={}", name, value),
+                None => name.clone()
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+
+        format!("{}?{}", url, query)
+    }
+
+    /// Fail on any option that was not recognized.
+    fn finish(self) -> Result<(), ProbeSpecError> {
+        match self.pairs.into_iter().next() {
+            Some((name, _)) if !self.passed_through => Err(ProbeSpecError::UnsupportedOption(name)),
+            _ => Ok(())
+        }
+    }
+}
+
+/// Parse a duration such as `30s`, `500ms`, `2m` or `1h`. A bare number is seconds.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount = amount.parse::<u64>().ok()?;
+
+    match unit {
+        "ms" => Some(Duration::from_millis(amount)),
+        "" | "s" => Some(Duration::from_secs(amount)),
+        "m" => amount.checked_mul(60).map(Duration::from_secs),
+        "h" => amount.checked_mul(60 * 60).map(Duration::from_secs),
+        _ => None
+    }
+}
+
+/// Status test for a spec: a specific status if given, otherwise any success.
+struct SpecStatus(Option<StatusCode>);
+
+impl StatusTest for SpecStatus {
+    fn test(&self, status: StatusCode) -> bool {
+        match self.0 {
+            Some(expected) => status == expected,
+            None => status.is_success()
+        }
+    }
+}
+
+/// Probe built from a [`ProbeSpec`].
+pub struct SpecProbe {
+    target: ProbeTarget,
+
+    /// Client for an HTTPS target, so that connections are reused between attempts.
+    https: Option<HttpClient>
+}
+
+impl SpecProbe {
+    async fn check(&self) -> Result<(), SpecProbeError> {
+        match &self.target {
+            ProbeTarget::Tcp { host, port } => {
+                TcpProbe::new(host.as_str(), *port).probe().await?;
+            }
+            ProbeTarget::Http { url, status } | ProbeTarget::Https { url, status, .. } => {
+                let client = self.https.clone()
+                    .unwrap_or_else(HttpClient::shared);
+
+                client.get_text_with(url.as_str(), &SpecStatus(*status)).await?;
+            }
+            #[cfg(unix)]
+            ProbeTarget::Unix { socket, path: None, .. } => {
+                super::connect_unix_socket(socket).await?;
+            }
+            #[cfg(unix)]
+            ProbeTarget::Unix { socket, path: Some(path), status } => {
+                crate::http::get_text_unix_with::<_, _, SpecStatus, _>(socket, path.as_str(), SpecStatus(*status)).await?;
+            }
+            #[cfg(not(unix))]
+            ProbeTarget::Unix { .. } => {
+                return Err(SpecProbeError::Unsupported("Unix domain sockets are not supported on this platform".into()));
+            }
+            ProbeTarget::Exec { program, args } => {
+                ExecProbe::new(program.as_str(), args).probe().await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Probe for SpecProbe {
+    type Output = ();
+    type Error = SpecProbeError;
+
+    fn probe(&self) -> ProbeFuture<'_, (), SpecProbeError> {
+        Box::pin(self.check())
+    }
+
+    fn is_permanent(&self, error: &SpecProbeError) -> bool {
+        match (&self.target, error) {
+            (ProbeTarget::Exec { program, args }, SpecProbeError::Exec(error)) => ExecProbe::new(program.as_str(), args).is_permanent(error),
+            (_, SpecProbeError::Unsupported(_)) => true,
+            _ => false
+        }
+    }
+}
+
+/// Wait until the target of a spec is ready, using the timeout of the spec, if
+/// any, as the overall deadline.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::{wait_for_spec, ProbeSpec};
+///
+/// async fn example() -> Result<(), Box<dyn std::error::Error>> {
+///     let spec: ProbeSpec = std::env::var("DATABASE_PROBE")?.parse()?;
+///
+///     wait_for_spec(&spec, Limit::new(30, Constant::new(Duration::from_secs(1)))).await?;
+///     Ok(())
+/// }
+/// ```
+pub async fn wait_for_spec<B: Backoff>(spec: &ProbeSpec, backoff: B) -> Result<(), WaitError<SpecProbeError>> {
+    wait_for_spec_with_options(spec, WaitOptions::new(backoff)).await
+}
+
+/// Wait until the target of a spec is ready. The timeout of the spec, if any, is
+/// the overall deadline unless the options already have one.
+pub async fn wait_for_spec_with_options<B: Backoff>(spec: &ProbeSpec, mut options: WaitOptions<B>) -> Result<(), WaitError<SpecProbeError>> {
+    info!("Will wait until {} is ready", spec);
+
+    if options.deadline.is_none() {
+        options.deadline = spec.timeout;
+    }
+
+    let probe = match spec.probe() {
+        Ok(probe) => probe,
+        Err(error) => {
+            return Err(WaitError::Permanent {
+                attempts: 0,
+                elapsed: Duration::ZERO,
+                error,
+                attempt_log: Vec::new()
+            });
+        }
+    };
+
+    wait_for_with_options(probe, options).await
+}
+
+#[cfg(test)]
+mod test_probe_spec {
+    use std::time::Duration;
+    use backoff::backoff::Constant;
+    use http::StatusCode;
+    use tokio::net::TcpListener;
+    use crate::retry::Limit;
+    use crate::waiter::{SpecProbeError, WaitError};
+    use super::{wait_for_spec, ProbeSpec, ProbeSpecError, ProbeTarget};
+
+    fn parse(s: &str) -> ProbeSpec {
+        s.parse().unwrap()
+    }
+
+    fn parse_err(s: &str) -> ProbeSpecError {
+        s.parse::<ProbeSpec>().unwrap_err()
+    }
+
+    fn backoff() -> Limit {
+        Limit::new(3, Constant::new(Duration::from_millis(10)))
+    }
+
+    #[test]
+    fn parses_exec() {
+        assert_eq!(
+            &ProbeTarget::Exec { program: "pg_isready".into(), args: vec!["-h".into(), "localhost".into()] },
+            parse("exec:pg_isready -h  localhost").target()
+        );
+
+        assert!(matches!(parse_err("exec: "), ProbeSpecError::MissingCommand));
+    }
+
+    #[test]
+    fn parses_exec_with_question_mark() {
+        let spec = parse("exec:curl -f http://x/health?ready=1&timeout=5s");
+
+        assert_eq!(
+            &ProbeTarget::Exec { program: "curl".into(), args: vec!["-f".into(), "http://x/health?ready=1&timeout=5s".into()] },
+            spec.target()
+        );
+        assert_eq!(None, spec.timeout());
+    }
+
+    #[test]
+    fn parses_exec_without_interpreting_quotes() {
+        assert_eq!(
+            &ProbeTarget::Exec { program: "sh".into(), args: vec!["-c".into(), "'exit".into(), "0'".into()] },
+            parse("exec:sh -c 'exit 0'").target()
+        );
+    }
+
+    #[test]
+    fn parses_http_keeping_other_parameters() {
+        let spec = parse("https://localhost/health?verbose=1&status=404&insecure=true");
+
+        assert_eq!(
+            &ProbeTarget::Https { url: "https://localhost/health?verbose=1".into(), status: Some(StatusCode::NOT_FOUND), insecure: true },
+            spec.target()
+        );
+        assert_eq!(None, spec.timeout());
+        assert_eq!("https://localhost/health?verbose=1&status=404&insecure=true", spec.to_string());
+    }
+
+    #[test]
+    fn parses_http_keeping_flags() {
+        assert_eq!(
+            &ProbeTarget::Http { url: "http://localhost/health?verbose&full=1".into(), status: None },
+            parse("http://localhost/health?verbose&full=1").target()
+        );
+
+        assert_eq!(
+            &ProbeTarget::Https { url: "https://localhost/health".into(), status: None, insecure: true },
+            parse("https://localhost/health?insecure").target()
+        );
+    }
+
+    #[test]
+    fn parses_tcp() {
+        let spec = parse("tcp://db:5432?timeout=500ms");
+
+        assert_eq!(&ProbeTarget::Tcp { host: "db".into(), port: 5432 }, spec.target());
+        assert_eq!(Some(Duration::from_millis(500)), spec.timeout());
+
+        assert_eq!(&ProbeTarget::Tcp { host: "::1".into(), port: 80 }, parse("tcp://[::1]:80").target());
+    }
+
+    #[test]
+    fn parses_timeouts() {
+        assert_eq!(Some(Duration::from_secs(30)), parse("tcp://a:1?timeout=30").timeout());
+        assert_eq!(Some(Duration::from_secs(120)), parse("tcp://a:1?timeout=2m").timeout());
+        assert_eq!(Some(Duration::from_secs(3600)), parse("tcp://a:1?timeout=1h").timeout());
+
+        assert!(matches!(parse_err("tcp://a:1?timeout=soon"), ProbeSpecError::InvalidValue { option, .. } if option == "timeout"));
+        assert!(matches!(parse_err("tcp://a:1?timeout=18446744073709551615h"), ProbeSpecError::InvalidValue { option, .. } if option == "timeout"));
+        assert!(matches!(parse_err("tcp://a:1?timeout"), ProbeSpecError::InvalidValue { option, .. } if option == "timeout"));
+    }
+
+    #[test]
+    fn parses_unix() {
+        assert_eq!(
+            &ProbeTarget::Unix { socket: "/var/run/docker.sock".into(), path: Some("/_ping".into()), status: None },
+            parse("unix:///var/run/docker.sock?path=/_ping").target()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid() {
+        assert!(matches!(parse_err("tcp://db"), ProbeSpecError::InvalidAddress(_)));
+        assert!(matches!(parse_err("tcp://db:http"), ProbeSpecError::InvalidAddress(_)));
+        assert!(matches!(parse_err("tcp://db:1?status=200"), ProbeSpecError::UnsupportedOption(option) if option == "status"));
+        assert!(matches!(parse_err("http://a?status=abc"), ProbeSpecError::InvalidValue { .. }));
+        assert!(matches!(parse_err("ftp://a"), ProbeSpecError::UnsupportedScheme(scheme) if scheme == "ftp"));
+    }
+
+    #[tokio::test]
+    async fn waits_for_http_status() {
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("GET", "/missing")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        wait_for_spec(&parse(&format!("{}/missing?status=404", server.url())), backoff())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn waits_for_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap();
+
+        let port = listener.local_addr()
+            .unwrap()
+            .port();
+
+        wait_for_spec(&parse(&format!("tcp://127.0.0.1:{}", port)), backoff())
+            .await
+            .unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn exec_exit_status() {
+        wait_for_spec(&parse("exec:true"), backoff())
+            .await
+            .unwrap();
+
+        let actual = wait_for_spec(&parse("exec:false"), backoff())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual, WaitError::Failed { attempts: 4, error: SpecProbeError::Exec(_), .. }), "{:?}", actual);
+    }
+
+    #[tokio::test]
+    async fn exec_missing_command_is_permanent() {
+        let actual = wait_for_spec(&parse("exec:passivized-no-such-command"), backoff())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual, WaitError::Permanent { attempts: 1, .. }), "{:?}", actual);
+    }
+
+    #[tokio::test]
+    async fn timeout_is_deadline() {
+        let port = {
+            let listener = TcpListener::bind("127.0.0.1:0")
+                .await
+                .unwrap();
+
+            listener.local_addr()
+                .unwrap()
+                .port()
+        };
+
+        let backoff = Limit::new(100, Constant::new(Duration::from_millis(50)));
+
+        let actual = wait_for_spec(&parse(&format!("tcp://127.0.0.1:{}?timeout=200ms", port)), backoff)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual, WaitError::TimedOut { .. }), "{:?}", actual);
+        assert!(actual.elapsed() < Duration::from_secs(1));
+    }
+}
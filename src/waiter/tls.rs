@@ -0,0 +1,199 @@
+use std::time::Duration;
+use backoff::backoff::{Backoff, Constant};
+use log::info;
+use tokio::net::TcpStream;
+
+use crate::imp::{TlsBackend, TlsImp};
+use crate::retry::Limit;
+use crate::tls::TlsConnector;
+use crate::tls_certificate_tests::{CertificateTest, PeerCertificate};
+use super::errors::TlsProbeError;
+use super::{wait_for_with_options, Probe, ProbeFuture, WaitError, WaitOptions};
+
+/// Probe a server by completing a TLS handshake, without sending any application
+/// data, returning the certificate the server presented.
+///
+/// Useful for TLS services that are not HTTP, such as LDAPS or SMTPS.
+pub struct TlsProbe {
+    host: String,
+    port: u16,
+    tls: TlsConnector,
+    certificate_tests: Vec<Box<dyn CertificateTest>>
+}
+
+impl TlsProbe {
+
+    pub fn new<H: Into<String>>(host: H, port: u16, tls: TlsConnector) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            tls,
+            certificate_tests: Vec::new()
+        }
+    }
+
+    /// Additionally require the certificate presented by the server to pass a test.
+    pub fn with_certificate_test<T: CertificateTest + 'static>(mut self, certificate_test: T) -> Self {
+        self.certificate_tests.push(Box::new(certificate_test));
+        self
+    }
+
+    async fn handshake(&self) -> Result<PeerCertificate, TlsProbeError> {
+        let stream = TcpStream::connect((self.host.as_str(), self.port))
+            .await
+            .map_err(TlsProbeError::Connect)?;
+
+        let der = TlsImp::handshake(&self.tls, &self.host, stream)
+            .await
+            .map_err(TlsProbeError::Handshake)?
+            .ok_or(TlsProbeError::NoCertificate)?;
+
+        let certificate = PeerCertificate::from_der(&der)
+            .map_err(TlsProbeError::InvalidCertificate)?;
+
+        if self.certificate_tests.iter().all(|test| test.test(&certificate)) {
+            Ok(certificate)
+        }
+        else {
+            Err(TlsProbeError::UnexpectedCertificate(certificate))
+        }
+    }
+
+}
+
+impl Probe for TlsProbe {
+    type Output = PeerCertificate;
+    type Error = TlsProbeError;
+
+    fn probe(&self) -> ProbeFuture<'_, PeerCertificate, TlsProbeError> {
+        Box::pin(self.handshake())
+    }
+}
+
+/// Wait until a TLS handshake with a server succeeds.
+///
+/// To also check the certificate the server presents, use a [`TlsProbe`].
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use passivized_test_support::tls::TlsConnector;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::tls_certificate_tests::{has_dns_name, is_valid_for};
+/// use passivized_test_support::waiter::{wait_for, wait_for_tls_server, TlsProbe};
+///
+/// async fn example() -> Result<(), Box<dyn std::error::Error>> {
+///     wait_for_tls_server("localhost", 636, TlsConnector::new()?).await?;
+///
+///     let probe = TlsProbe::new("localhost", 5432, TlsConnector::new()?)
+///         .with_certificate_test(has_dns_name("db.test"))
+///         .with_certificate_test(is_valid_for(Duration::from_secs(86_400)));
+///
+///     let certificate = wait_for(probe, Limit::new(5, Constant::new(Duration::from_secs(2)))).await?;
+///
+///     println!("Certificate expires {}", certificate.not_after);
+///     Ok(())
+/// }
+/// ```
+pub async fn wait_for_tls_server(host: &str, port: u16, tls: TlsConnector) -> Result<PeerCertificate, WaitError<TlsProbeError>> {
+    let interval = Duration::from_secs(2);
+    let backoff = Limit::new(4, Constant::new(interval));
+
+    wait_for_tls_server_with_options(host, port, tls, WaitOptions::new(backoff)).await
+}
+
+pub async fn wait_for_tls_server_with_options<B: Backoff>(host: &str, port: u16, tls: TlsConnector, options: WaitOptions<B>) -> Result<PeerCertificate, WaitError<TlsProbeError>> {
+    info!("Will wait until can complete TLS handshake with {}:{}", host, port);
+
+    wait_for_with_options(TlsProbe::new(host, port, tls), options).await
+}
+
+#[cfg(test)]
+mod test_tls_probe {
+    use std::time::Duration;
+    use backoff::backoff::Constant;
+    use tokio::net::TcpListener;
+    use crate::retry::Limit;
+    use crate::tls::TlsConnector;
+    use crate::tls::test_server::{accept, connector};
+    use crate::tls_certificate_tests::has_dns_name;
+    use crate::waiter::{wait_for, TlsProbeError, WaitError, WaitOptions};
+    use super::{wait_for_tls_server_with_options, TlsProbe};
+
+    /// Accept TLS connections until the test ends, returning the port.
+    async fn serve() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap();
+
+        let port = listener.local_addr()
+            .unwrap()
+            .port();
+
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept()
+                    .await
+                    .unwrap();
+
+                tokio::spawn(async move {
+                    let _ = accept(stream).await;
+                });
+            }
+        });
+
+        port
+    }
+
+    fn backoff() -> Limit {
+        Limit::new(1, Constant::new(Duration::from_millis(10)))
+    }
+
+    #[tokio::test]
+    async fn completes_handshake() {
+        let port = serve().await;
+
+        let actual = wait_for_tls_server_with_options("localhost", port, connector(), WaitOptions::new(backoff()))
+            .await
+            .unwrap();
+
+        assert_eq!(vec!["localhost"], actual.dns_names);
+    }
+
+    #[tokio::test]
+    async fn fails_when_certificate_unexpected() {
+        let port = serve().await;
+
+        let probe = TlsProbe::new("localhost", port, connector())
+            .with_certificate_test(has_dns_name("example.com"));
+
+        let actual = wait_for(probe, backoff())
+            .await
+            .unwrap_err();
+
+        if let WaitError::Failed { error: TlsProbeError::UnexpectedCertificate(certificate), .. } = actual {
+            assert_eq!("CN=localhost, O=passivized_test_support", certificate.subject);
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_when_certificate_untrusted() {
+        let port = serve().await;
+
+        let actual = wait_for_tls_server_with_options("localhost", port, TlsConnector::new().unwrap(), WaitOptions::new(backoff()))
+            .await
+            .unwrap_err();
+
+        if let WaitError::Failed { error, .. } = actual {
+            assert!(matches!(error, TlsProbeError::Handshake(_)), "Unexpected error: {:?}", error);
+        }
+        else {
+            panic!("Unexpected error: {:?}", actual);
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use std::path::{Path, PathBuf};
+use backoff::backoff::Backoff;
+use log::info;
+use tokio::net::UnixStream;
+
+use crate::http_errors::HttpError;
+use crate::http_status_tests::StatusTest;
+use super::{wait_for_with_options, Probe, ProbeFuture, WaitError, WaitOptions};
+
+pub async fn connect_unix_socket<P: AsRef<Path>>(path: P) -> Result<(), std::io::Error> {
+    let connection = UnixStream::connect(path).await?;
+    drop(connection);
+    Ok(())
+}
+
+/// Probe a server by opening, and then immediately closing, a connection to a
+/// Unix domain socket.
+pub struct UnixSocketProbe {
+    path: PathBuf
+}
+
+impl UnixSocketProbe {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into()
+        }
+    }
+}
+
+impl Probe for UnixSocketProbe {
+    type Output = ();
+    type Error = std::io::Error;
+
+    fn probe(&self) -> ProbeFuture<'_, (), std::io::Error> {
+        Box::pin(connect_unix_socket(&self.path))
+    }
+}
+
+/// Probe an HTTP server listening on a Unix domain socket, returning the response body.
+pub struct UnixHttpProbe<T> {
+    socket: PathBuf,
+    path: String,
+    status_test: T
+}
+
+impl<T: StatusTest> UnixHttpProbe<T> {
+    /// The path is the path and query of the request, such as `/_ping`.
+    pub fn new<S, U>(socket: S, path: U, status_test: T) -> Self
+    where
+        S: Into<PathBuf>,
+        U: Into<String>
+    {
+        Self {
+            socket: socket.into(),
+            path: path.into(),
+            status_test
+        }
+    }
+}
+
+impl<T: StatusTest> Probe for UnixHttpProbe<T> {
+    type Output = String;
+    type Error = HttpError;
+
+    fn probe(&self) -> ProbeFuture<'_, String, HttpError> {
+        Box::pin(crate::http::get_text_unix_with::<_, _, T, _>(&self.socket, self.path.as_str(), &self.status_test))
+    }
+}
+
+/// Wait until a connection to a Unix domain socket succeeds.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::wait_for_unix_socket;
+///
+/// async fn example() -> Result<(), std::io::Error> {
+///     let backoff = Limit::new(4, Constant::new(Duration::from_secs(2)));
+///
+///     wait_for_unix_socket("/var/run/docker.sock", backoff).await
+/// }
+/// ```
+pub async fn wait_for_unix_socket<P, B>(path: P, backoff: B) -> Result<(), std::io::Error>
+where
+    P: Into<PathBuf>,
+    B: Backoff
+{
+    wait_for_unix_socket_with_options(path, WaitOptions::new(backoff))
+        .await
+        .map_err(std::io::Error::from)
+}
+
+pub async fn wait_for_unix_socket_with_options<P, B>(path: P, options: WaitOptions<B>) -> Result<(), WaitError<std::io::Error>>
+where
+    P: Into<PathBuf>,
+    B: Backoff
+{
+    let probe = UnixSocketProbe::new(path);
+
+    info!("Will wait until can connect to {}", probe.path.display());
+
+    wait_for_with_options(probe, options).await
+}
+
+/// Wait for an HTTP server listening on a Unix domain socket to be available,
+/// and the path to return an HTTP status code that meets an expectation.
+///
+/// # Example
+///
+/// ```rust
+/// use std::error::Error;
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use passivized_test_support::http_status_tests::is_success;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::wait_for_unix_http_server;
+///
+/// async fn example() -> Result<(), Box<dyn Error>> {
+///     let backoff = Limit::new(4, Constant::new(Duration::from_secs(2)));
+///
+///     wait_for_unix_http_server("/var/run/docker.sock", "/_ping", is_success(), backoff).await?;
+///     Ok(())
+/// }
+/// ```
+pub async fn wait_for_unix_http_server<S, U, T, B>(socket: S, path: U, status_test: T, backoff: B) -> Result<String, HttpError>
+where
+    S: Into<PathBuf>,
+    U: Into<String>,
+    T: StatusTest,
+    B: Backoff
+{
+    wait_for_unix_http_server_with_options(socket, path, status_test, WaitOptions::new(backoff))
+        .await
+        .map_err(HttpError::from)
+}
+
+pub async fn wait_for_unix_http_server_with_options<S, U, T, B>(socket: S, path: U, status_test: T, options: WaitOptions<B>) -> Result<String, WaitError<HttpError>>
+where
+    S: Into<PathBuf>,
+    U: Into<String>,
+    T: StatusTest,
+    B: Backoff
+{
+    let probe = UnixHttpProbe::new(socket, path, status_test);
+
+    info!("Will wait until can connect to {} on {}", probe.path, probe.socket.display());
+
+    wait_for_with_options(probe, options).await
+}
+
+#[cfg(test)]
+mod test_wait_for_unix {
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use backoff::backoff::Constant;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::UnixListener;
+    use crate::http_status_tests::is_success;
+    use crate::retry::Limit;
+    use crate::timestamps::named;
+    use super::{wait_for_unix_http_server, wait_for_unix_socket};
+
+    fn backoff() -> Limit {
+        Limit::new(2, Constant::new(Duration::from_millis(10)))
+    }
+
+    fn socket_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("{}-{}.sock", named(name), std::process::id()))
+    }
+
+    #[tokio::test]
+    async fn fails_when_socket_missing() {
+        wait_for_unix_socket(socket_path("missing"), backoff())
+            .await
+            .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn waits_for_http_server() {
+        let path = socket_path("http");
+        let listener = UnixListener::bind(&path)
+            .unwrap();
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept()
+                .await
+                .unwrap();
+
+            let mut request = [0u8; 1024];
+            let read = stream.read(&mut request)
+                .await
+                .unwrap();
+
+            assert!(request[..read].starts_with(b"GET /_ping HTTP/1.1\r\n"));
+
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nOK")
+                .await
+                .unwrap();
+        });
+
+        let actual = wait_for_unix_http_server(&path, "/_ping", is_success(), backoff())
+            .await
+            .unwrap();
+
+        assert_eq!("OK", actual);
+
+        server.await
+            .unwrap();
+
+        std::fs::remove_file(path)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn waits_for_socket() {
+        let path = socket_path("socket");
+        let _listener = UnixListener::bind(&path)
+            .unwrap();
+
+        wait_for_unix_socket(&path, backoff())
+            .await
+            .unwrap();
+
+        std::fs::remove_file(path)
+            .unwrap();
+    }
+}
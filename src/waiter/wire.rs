@@ -0,0 +1,618 @@
+use backoff::backoff::Backoff;
+use log::info;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use super::errors::WireProbeError;
+use super::{wait_for_with_options, Probe, ProbeFuture, WaitError, WaitOptions};
+
+/// Largest response read while looking for an expected reply.
+const MAX_RESPONSE: usize = 64 * 1024;
+
+/// Probe a server by connecting, optionally sending some bytes, and expecting the
+/// response to start with a given prefix, such as the banner of an SSH or SMTP server.
+///
+/// Returns the bytes read, which are at least as long as the prefix.
+pub struct BannerProbe {
+    host: String,
+    port: u16,
+    send: Vec<u8>,
+    expected_prefix: Vec<u8>
+}
+
+impl BannerProbe {
+    pub fn new<H, S, E>(host: H, port: u16, send: S, expected_prefix: E) -> Self
+    where
+        H: Into<String>,
+        S: Into<Vec<u8>>,
+        E: Into<Vec<u8>>
+    {
+        Self {
+            host: host.into(),
+            port,
+            send: send.into(),
+            expected_prefix: expected_prefix.into()
+        }
+    }
+
+    async fn exchange(&self) -> Result<Vec<u8>, WireProbeError> {
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+
+        if !self.send.is_empty() {
+            stream.write_all(&self.send).await?;
+        }
+
+        let mut received = Vec::new();
+
+        while received.len() < self.expected_prefix.len() {
+            let mut buffer = [0u8; 1024];
+            let count = stream.read(&mut buffer).await?;
+
+            if count == 0 {
+                return Err(WireProbeError::Closed);
+            }
+
+            received.extend_from_slice(&buffer[..count]);
+        }
+
+        if received.starts_with(&self.expected_prefix) {
+            Ok(received)
+        }
+        else {
+            Err(WireProbeError::Unexpected(String::from_utf8_lossy(&received).into_owned()))
+        }
+    }
+}
+
+impl Probe for BannerProbe {
+    type Output = Vec<u8>;
+    type Error = WireProbeError;
+
+    fn probe(&self) -> ProbeFuture<'_, Vec<u8>, WireProbeError> {
+        Box::pin(self.exchange())
+    }
+}
+
+/// Probe a Redis server by sending `PING` and expecting `+PONG`.
+///
+/// A server that requires authentication replies `-NOAUTH`, which is also
+/// accepted, because the server is ready to accept commands once authenticated.
+/// Any other error reply, such as `-LOADING` while a data set is loading, is
+/// reported as not ready.
+pub struct RedisProbe {
+    host: String,
+    port: u16
+}
+
+impl RedisProbe {
+    pub fn new<H: Into<String>>(host: H, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port
+        }
+    }
+
+    async fn ping(&self) -> Result<(), WireProbeError> {
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+
+        stream.write_all(b"*1\r\n$4\r\nPING\r\n").await?;
+
+        let reply = read_line(&mut stream).await?;
+
+        if reply == "+PONG" || reply.starts_with("-NOAUTH") {
+            Ok(())
+        }
+        else if let Some(error) = reply.strip_prefix('-') {
+            Err(WireProbeError::NotReady(error.to_string()))
+        }
+        else {
+            Err(WireProbeError::Unexpected(reply))
+        }
+    }
+}
+
+impl Probe for RedisProbe {
+    type Output = ();
+    type Error = WireProbeError;
+
+    fn probe(&self) -> ProbeFuture<'_, (), WireProbeError> {
+        Box::pin(self.ping())
+    }
+}
+
+/// Probe a Postgres server the way `pg_isready` does, by starting a session and
+/// checking the first reply.
+///
+/// An `SSLRequest` is sent first; if the server offers TLS, the session is instead
+/// started on a new plain connection. An authentication request, or any error
+/// other than "cannot connect now" (such as "the database system is starting up")
+/// means the server is ready. No password is sent.
+pub struct PostgresProbe {
+    host: String,
+    port: u16,
+    user: String
+}
+
+/// Postgres SSLRequest message: length 8, then request code 80877103.
+const POSTGRES_SSL_REQUEST: [u8; 8] = [0, 0, 0, 8, 0x04, 0xd2, 0x16, 0x2f];
+
+/// Postgres SQLSTATE for when the server is starting up, shutting down, or in recovery.
+const POSTGRES_CANNOT_CONNECT_NOW: &str = "57P03";
+
+impl PostgresProbe {
+    pub fn new<H: Into<String>>(host: H, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            user: "postgres".into()
+        }
+    }
+
+    /// User name to start the session as. Defaults to `postgres`.
+    pub fn with_user<U: Into<String>>(mut self, user: U) -> Self {
+        self.user = user.into();
+        self
+    }
+
+    async fn start_session(&self) -> Result<(), WireProbeError> {
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+
+        stream.write_all(&POSTGRES_SSL_REQUEST).await?;
+
+        match read_byte(&mut stream).await? {
+            b'N' => {},
+            b'S' => {
+                stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+            }
+            other => {
+                return Err(WireProbeError::Unexpected(format!("SSLRequest reply {:?}", other as char)));
+            }
+        }
+
+        stream.write_all(&self.startup_message()).await?;
+
+        let message_type = read_byte(&mut stream).await?;
+        let length = stream.read_u32().await? as usize;
+
+        if !(4..=MAX_RESPONSE).contains(&length) {
+            return Err(WireProbeError::Unexpected(format!("message length {}", length)));
+        }
+
+        let mut body = vec![0u8; length - 4];
+        stream.read_exact(&mut body).await?;
+
+        match message_type {
+            b'R' => Ok(()),
+            b'E' => {
+                let fields = postgres_error_fields(&body);
+
+                let code = fields.iter()
+                    .find(|(field, _)| *field == b'C')
+                    .map(|(_, value)| value.as_str());
+
+                if code == Some(POSTGRES_CANNOT_CONNECT_NOW) {
+                    let message = fields.into_iter()
+                        .find(|(field, _)| *field == b'M')
+                        .map(|(_, value)| value)
+                        .unwrap_or_default();
+
+                    Err(WireProbeError::NotReady(message))
+                }
+                else {
+                    Ok(())
+                }
+            }
+            other => Err(WireProbeError::Unexpected(format!("startup reply {:?}", other as char)))
+        }
+    }
+
+    fn startup_message(&self) -> Vec<u8> {
+        // Protocol version 3.0
+        let mut body: Vec<u8> = vec![0, 3, 0, 0];
+
+        body.extend_from_slice(b"user\0");
+        body.extend_from_slice(self.user.as_bytes());
+        body.extend_from_slice(b"\0\0");
+
+        let mut message = ((body.len() + 4) as u32).to_be_bytes().to_vec();
+        message.extend(body);
+        message
+    }
+}
+
+/// Fields of an ErrorResponse body, each a type code and a null-terminated string.
+fn postgres_error_fields(body: &[u8]) -> Vec<(u8, String)> {
+    let mut fields = Vec::new();
+    let mut remaining = body;
+
+    while let Some((&field, rest)) = remaining.split_first() {
+        if field == 0 {
+            break;
+        }
+
+        let end = rest.iter()
+            .position(|b| *b == 0)
+            .unwrap_or(rest.len());
+
+        fields.push((field, String::from_utf8_lossy(&rest[..end]).into_owned()));
+        remaining = rest.get(end + 1..).unwrap_or_default();
+    }
+
+    fields
+}
+
+impl Probe for PostgresProbe {
+    type Output = ();
+    type Error = WireProbeError;
+
+    fn probe(&self) -> ProbeFuture<'_, (), WireProbeError> {
+        Box::pin(self.start_session())
+    }
+}
+
+/// Probe a MySQL or MariaDB server by reading the initial handshake packet it
+/// sends on connect, returning the server version.
+///
+/// An error packet, such as "Too many connections" or "Host is blocked", is
+/// reported as not ready.
+pub struct MySqlProbe {
+    host: String,
+    port: u16
+}
+
+impl MySqlProbe {
+    pub fn new<H: Into<String>>(host: H, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port
+        }
+    }
+
+    async fn read_handshake(&self) -> Result<String, WireProbeError> {
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+
+        // Three byte little-endian payload length, then a sequence number.
+        let mut header = [0u8; 4];
+        stream.read_exact(&mut header).await?;
+
+        let length = u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize;
+
+        if length == 0 || length > MAX_RESPONSE {
+            return Err(WireProbeError::Unexpected(format!("packet length {}", length)));
+        }
+
+        let mut payload = vec![0u8; length];
+        stream.read_exact(&mut payload).await?;
+
+        match payload[0] {
+            // Protocol version 10
+            0x0a => {
+                let version = &payload[1..];
+                let end = version.iter()
+                    .position(|b| *b == 0)
+                    .unwrap_or(version.len());
+
+                Ok(String::from_utf8_lossy(&version[..end]).into_owned())
+            }
+            // Error packet: marker, two byte error code, then the message.
+            0xff => {
+                Err(WireProbeError::NotReady(String::from_utf8_lossy(payload.get(3..).unwrap_or_default()).into_owned()))
+            }
+            other => Err(WireProbeError::Unexpected(format!("handshake protocol {}", other)))
+        }
+    }
+}
+
+impl Probe for MySqlProbe {
+    type Output = String;
+    type Error = WireProbeError;
+
+    fn probe(&self) -> ProbeFuture<'_, String, WireProbeError> {
+        Box::pin(self.read_handshake())
+    }
+}
+
+async fn read_byte<R: AsyncRead + Unpin>(reader: &mut R) -> Result<u8, WireProbeError> {
+    let mut buffer = [0u8; 1];
+
+    match reader.read(&mut buffer).await? {
+        0 => Err(WireProbeError::Closed),
+        _ => Ok(buffer[0])
+    }
+}
+
+/// Read a line terminated by CRLF, without the terminator.
+async fn read_line<R: AsyncRead + Unpin>(reader: &mut R) -> Result<String, WireProbeError> {
+    let mut line = Vec::new();
+
+    while !line.ends_with(b"\r\n") {
+        if line.len() > MAX_RESPONSE {
+            return Err(WireProbeError::Unexpected(String::from_utf8_lossy(&line).into_owned()));
+        }
+
+        line.push(read_byte(reader).await?);
+    }
+
+    line.truncate(line.len() - 2);
+
+    Ok(String::from_utf8_lossy(&line).into_owned())
+}
+
+/// Wait until a server sends a response that starts with an expected prefix.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::{wait_for_tcp_banner, WaitError, WireProbeError};
+///
+/// async fn example() -> Result<(), WaitError<WireProbeError>> {
+///     let backoff = Limit::new(10, Constant::new(Duration::from_secs(1)));
+///
+///     // Nothing to send; an SMTP server greets first.
+///     wait_for_tcp_banner("localhost", 25, "", "220 ", backoff).await?;
+///     Ok(())
+/// }
+/// ```
+pub async fn wait_for_tcp_banner<S, E, B>(host: &str, port: u16, send: S, expected_prefix: E, backoff: B) -> Result<Vec<u8>, WaitError<WireProbeError>>
+where
+    S: Into<Vec<u8>>,
+    E: Into<Vec<u8>>,
+    B: Backoff
+{
+    wait_for_tcp_banner_with_options(host, port, send, expected_prefix, WaitOptions::new(backoff)).await
+}
+
+pub async fn wait_for_tcp_banner_with_options<S, E, B>(host: &str, port: u16, send: S, expected_prefix: E, options: WaitOptions<B>) -> Result<Vec<u8>, WaitError<WireProbeError>>
+where
+    S: Into<Vec<u8>>,
+    E: Into<Vec<u8>>,
+    B: Backoff
+{
+    info!("Will wait until {}:{} sends the expected response", host, port);
+
+    wait_for_with_options(BannerProbe::new(host, port, send, expected_prefix), options).await
+}
+
+/// Wait until a Redis server replies to `PING`.
+pub async fn wait_for_redis<B: Backoff>(host: &str, port: u16, backoff: B) -> Result<(), WaitError<WireProbeError>> {
+    wait_for_redis_with_options(host, port, WaitOptions::new(backoff)).await
+}
+
+pub async fn wait_for_redis_with_options<B: Backoff>(host: &str, port: u16, options: WaitOptions<B>) -> Result<(), WaitError<WireProbeError>> {
+    info!("Will wait until Redis at {}:{} is ready", host, port);
+
+    wait_for_with_options(RedisProbe::new(host, port), options).await
+}
+
+/// Wait until a Postgres server accepts sessions.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use backoff::backoff::Constant;
+/// use passivized_test_support::retry::Limit;
+/// use passivized_test_support::waiter::{wait_for_postgres, WaitError, WireProbeError};
+///
+/// async fn example() -> Result<(), WaitError<WireProbeError>> {
+///     let backoff = Limit::new(30, Constant::new(Duration::from_secs(1)));
+///
+///     wait_for_postgres("localhost", 5432, backoff).await
+/// }
+/// ```
+pub async fn wait_for_postgres<B: Backoff>(host: &str, port: u16, backoff: B) -> Result<(), WaitError<WireProbeError>> {
+    wait_for_postgres_with_options(host, port, WaitOptions::new(backoff)).await
+}
+
+pub async fn wait_for_postgres_with_options<B: Backoff>(host: &str, port: u16, options: WaitOptions<B>) -> Result<(), WaitError<WireProbeError>> {
+    info!("Will wait until Postgres at {}:{} is ready", host, port);
+
+    wait_for_with_options(PostgresProbe::new(host, port), options).await
+}
+
+/// Wait until a MySQL or MariaDB server sends its handshake, returning the server version.
+pub async fn wait_for_mysql<B: Backoff>(host: &str, port: u16, backoff: B) -> Result<String, WaitError<WireProbeError>> {
+    wait_for_mysql_with_options(host, port, WaitOptions::new(backoff)).await
+}
+
+pub async fn wait_for_mysql_with_options<B: Backoff>(host: &str, port: u16, options: WaitOptions<B>) -> Result<String, WaitError<WireProbeError>> {
+    info!("Will wait until MySQL at {}:{} is ready", host, port);
+
+    wait_for_with_options(MySqlProbe::new(host, port), options).await
+}
+
+#[cfg(test)]
+mod test_wire_probes {
+    use std::future::Future;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use crate::waiter::{Probe, WireProbeError};
+    use super::{BannerProbe, MySqlProbe, PostgresProbe, RedisProbe};
+
+    /// Fake server that handles each connection with a handler, returning the port.
+    async fn serve<F, Fut>(handler: F) -> u16
+    where
+        F: Fn(TcpStream) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static
+    {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap();
+
+        let port = listener.local_addr()
+            .unwrap()
+            .port();
+
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept()
+                    .await
+                    .unwrap();
+
+                tokio::spawn(handler(stream));
+            }
+        });
+
+        port
+    }
+
+    async fn redis_replying(reply: &'static str) -> u16 {
+        serve(move |mut stream| async move {
+            let mut request = [0u8; 14];
+            stream.read_exact(&mut request).await.unwrap();
+            assert_eq!(b"*1\r\n$4\r\nPING\r\n", &request);
+
+            stream.write_all(reply.as_bytes()).await.unwrap();
+        }).await
+    }
+
+    async fn postgres_replying(reply: Vec<u8>) -> u16 {
+        serve(move |mut stream| {
+            let reply = reply.clone();
+
+            async move {
+                let mut ssl_request = [0u8; 8];
+                stream.read_exact(&mut ssl_request).await.unwrap();
+                stream.write_all(b"N").await.unwrap();
+
+                let length = stream.read_u32().await.unwrap() as usize;
+                let mut startup = vec![0u8; length - 4];
+                stream.read_exact(&mut startup).await.unwrap();
+                assert_eq!(b"\0\x03\0\0user\0postgres\0\0", startup.as_slice());
+
+                stream.write_all(&reply).await.unwrap();
+            }
+        }).await
+    }
+
+    fn postgres_error(code: &str, message: &str) -> Vec<u8> {
+        let body = format!("SFATAL\0C{}\0M{}\0\0", code, message);
+
+        let mut reply = vec![b'E'];
+        reply.extend(((body.len() + 4) as u32).to_be_bytes());
+        reply.extend(body.as_bytes());
+        reply
+    }
+
+    async fn mysql_sending(payload: &'static [u8]) -> u16 {
+        serve(move |mut stream| async move {
+            let mut packet = (payload.len() as u32).to_le_bytes();
+            packet[3] = 0;
+
+            stream.write_all(&packet).await.unwrap();
+            stream.write_all(payload).await.unwrap();
+        }).await
+    }
+
+    #[tokio::test]
+    async fn banner_expected() {
+        let port = serve(|mut stream| async move {
+            stream.write_all(b"SSH-2.0-Fake\r\n").await.unwrap();
+        }).await;
+
+        let actual = BannerProbe::new("127.0.0.1", port, "", "SSH-2.0-")
+            .probe()
+            .await
+            .unwrap();
+
+        assert!(actual.starts_with(b"SSH-2.0-"));
+    }
+
+    #[tokio::test]
+    async fn banner_unexpected() {
+        let port = serve(|mut stream| async move {
+            let mut request = [0u8; 5];
+            stream.read_exact(&mut request).await.unwrap();
+            stream.write_all(b"nope!").await.unwrap();
+        }).await;
+
+        let actual = BannerProbe::new("127.0.0.1", port, "hello", "hello")
+            .probe()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual, WireProbeError::Unexpected(ref reply) if reply == "nope!"), "{:?}", actual);
+    }
+
+    #[tokio::test]
+    async fn mysql_error_packet() {
+        let port = mysql_sending(b"\xff\x10\x04Too many connections").await;
+
+        let actual = MySqlProbe::new("127.0.0.1", port)
+            .probe()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual, WireProbeError::NotReady(ref message) if message == "Too many connections"), "{:?}", actual);
+    }
+
+    #[tokio::test]
+    async fn mysql_handshake() {
+        let port = mysql_sending(b"\x0a8.0.36\0\x08\0\0\0abcdefgh\0").await;
+
+        let actual = MySqlProbe::new("127.0.0.1", port)
+            .probe()
+            .await
+            .unwrap();
+
+        assert_eq!("8.0.36", actual);
+    }
+
+    #[tokio::test]
+    async fn postgres_authentication_request() {
+        // AuthenticationCleartextPassword
+        let port = postgres_replying(vec![b'R', 0, 0, 0, 8, 0, 0, 0, 3]).await;
+
+        PostgresProbe::new("127.0.0.1", port)
+            .probe()
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn postgres_other_error_is_ready() {
+        let port = postgres_replying(postgres_error("28000", "role \"postgres\" does not exist")).await;
+
+        PostgresProbe::new("127.0.0.1", port)
+            .probe()
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn postgres_starting_up() {
+        let port = postgres_replying(postgres_error("57P03", "the database system is starting up")).await;
+
+        let actual = PostgresProbe::new("127.0.0.1", port)
+            .probe()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual, WireProbeError::NotReady(ref message) if message == "the database system is starting up"), "{:?}", actual);
+    }
+
+    #[tokio::test]
+    async fn redis_loading() {
+        let port = redis_replying("-LOADING Redis is loading the dataset in memory\r\n").await;
+
+        let actual = RedisProbe::new("127.0.0.1", port)
+            .probe()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual, WireProbeError::NotReady(ref message) if message.starts_with("LOADING")), "{:?}", actual);
+    }
+
+    #[tokio::test]
+    async fn redis_pong() {
+        let port = redis_replying("+PONG\r\n").await;
+
+        RedisProbe::new("127.0.0.1", port)
+            .probe()
+            .await
+            .unwrap();
+    }
+}